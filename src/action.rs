@@ -0,0 +1,56 @@
+use crate::status::Statuses;
+
+/// Something done on a turn, every action takes its own time
+#[derive(Clone, Copy)]
+pub enum Action {
+    /// Step on a cell with the given move cost of the terrain
+    Move(f64),
+    Attack,
+    /// Shoot or throw a missile
+    Shoot,
+    PickUp,
+    /// Take a tool or a weapon into the hands
+    Equip,
+    /// Wait or rest for a turn
+    Rest,
+    /// Open or close a door
+    Door,
+    Search,
+    Dig,
+    /// Tunnel through rock with bare claws
+    Burrow,
+    Refuel,
+    /// Special action of the character
+    Ability,
+}
+
+impl Action {
+    /// Time the action takes at the usual speed
+    fn base_time(self) -> f64 {
+        match self {
+            Self::Move(cost) => cost,
+            Self::Attack => 1.0,
+            Self::Shoot => 1.0,
+            Self::PickUp => 0.5,
+            Self::Equip => 1.0,
+            Self::Rest => 1.0,
+            Self::Door => 1.0,
+            Self::Search => 3.0,
+            Self::Dig => 1.0,
+            Self::Burrow => 3.0,
+            Self::Refuel => 2.0,
+            Self::Ability => 1.0,
+        }
+    }
+
+    /// Time the action takes, statuses change the speed of everything
+    /// and walking is also scaled, like by a heavy load
+    pub fn time(self, statuses: &Statuses, walk_scale: f64) -> f64 {
+        let time = self.base_time() * statuses.time_scale();
+
+        match self {
+            Self::Move(_) => time * walk_scale,
+            _ => time,
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::colors::{palette, RGBA};
+use crate::draw::{BTerm, DrawWithFov, Fov, Point};
+
+/// Time of animations. It runs with frames independently of the game clock
+/// and can be paused or slowed down
+pub struct AnimationClock {
+    /// Milliseconds passed since the previous frame
+    delta_ms: f64,
+    time_scale: f64,
+    paused: bool,
+}
+
+impl AnimationClock {
+    pub fn new(time_scale: f64) -> Self {
+        Self {
+            delta_ms: 0.0,
+            time_scale,
+            paused: false,
+        }
+    }
+
+    pub fn advance(&mut self, frame_time_ms: f32) {
+        self.delta_ms = if self.paused {
+            0.0
+        } else {
+            f64::max(frame_time_ms as f64 * self.time_scale, 0.0)
+        };
+    }
+
+    pub fn delta_ms(&self) -> f64 {
+        self.delta_ms
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+}
+
+const LUNGE_MS: f64 = 120.0;
+const HIT_FLASH_MS: f64 = 150.0;
+const DEATH_FADE_MS: f64 = 300.0;
+
+enum TweenKind {
+    /// Attacker jumps to the target cell and back
+    Lunge {
+        from: Point,
+        to: Point,
+        glyph: char,
+        color: RGBA,
+    },
+    /// Background of the hit cell blinks
+    HitFlash { pos: Point },
+    /// Killed creature fades away
+    DeathFade {
+        pos: Point,
+        glyph: char,
+        color: RGBA,
+    },
+}
+
+/// Short animation drawn over the field
+struct Tween {
+    kind: TweenKind,
+    elapsed_ms: f64,
+    duration_ms: f64,
+}
+
+impl Tween {
+    /// Part of the animation passed, from 0 to 1
+    fn progress(&self) -> f32 {
+        (self.elapsed_ms / self.duration_ms).min(1.0) as f32
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed_ms >= self.duration_ms
+    }
+}
+
+impl DrawWithFov for Tween {
+    fn draw_with_fov(&self, ctx: &mut BTerm, fov: &Fov, pos: Point, fov_pos: Point) {
+        let progress = self.progress();
+
+        match self.kind {
+            TweenKind::Lunge {
+                from,
+                to,
+                glyph,
+                color,
+            } => {
+                if !fov.contains(&(from + fov_pos)) || progress >= 0.5 {
+                    return;
+                }
+
+                let (from, to) = (from + pos, to + pos);
+                ctx.print_color(from.x, from.y, palette().bg, palette().bg, ' ');
+                ctx.print_color(to.x, to.y, color, palette().bg, glyph);
+            }
+            TweenKind::HitFlash { pos: p } => {
+                if fov.contains(&(p + fov_pos)) {
+                    let p = p + pos;
+                    ctx.set_bg(p.x, p.y, palette().hit_flash.lerp(palette().bg, progress));
+                }
+            }
+            TweenKind::DeathFade {
+                pos: p,
+                glyph,
+                color,
+            } => {
+                if fov.contains(&(p + fov_pos)) {
+                    let p = p + pos;
+                    let color = color.lerp(palette().bg, progress);
+                    ctx.print_color(p.x, p.y, color, palette().bg, glyph);
+                }
+            }
+        }
+    }
+}
+
+/// Running tweens, the game waits for them to finish before the next turn
+pub struct Animations {
+    tweens: Vec<Tween>,
+    enabled: bool,
+}
+
+impl Animations {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            tweens: Vec::new(),
+            enabled,
+        }
+    }
+
+    /// Queue the tween of a cell in view. Unseen cells are skipped,
+    /// so the game never waits for an animation nobody sees
+    fn add(&mut self, fov: &Fov, pos: Point, kind: TweenKind, duration_ms: f64) {
+        if self.enabled && fov.contains(&pos) {
+            self.tweens.push(Tween {
+                kind,
+                elapsed_ms: 0.0,
+                duration_ms,
+            });
+        }
+    }
+
+    pub fn lunge(&mut self, fov: &Fov, from: Point, to: Point, glyph: char, color: RGBA) {
+        let kind = TweenKind::Lunge {
+            from,
+            to,
+            glyph,
+            color,
+        };
+        self.add(fov, from, kind, LUNGE_MS);
+    }
+
+    pub fn hit_flash(&mut self, fov: &Fov, pos: Point) {
+        self.add(fov, pos, TweenKind::HitFlash { pos }, HIT_FLASH_MS);
+    }
+
+    pub fn death_fade(&mut self, fov: &Fov, pos: Point, glyph: char, color: RGBA) {
+        let kind = TweenKind::DeathFade { pos, glyph, color };
+        self.add(fov, pos, kind, DEATH_FADE_MS);
+    }
+
+    pub fn process(&mut self, clock: &AnimationClock) {
+        for tween in &mut self.tweens {
+            tween.elapsed_ms += clock.delta_ms();
+        }
+
+        self.tweens.retain(|t| !t.is_finished());
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.tweens.is_empty()
+    }
+}
+
+impl DrawWithFov for Animations {
+    fn draw_with_fov(&self, ctx: &mut BTerm, fov: &Fov, pos: Point, fov_pos: Point) {
+        for tween in &self.tweens {
+            tween.draw_with_fov(ctx, fov, pos, fov_pos);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::combat::CombatStats;
+use crate::item::{Inventory, ItemKind};
+
+/// The player sees lit cells up to this distance
+const SIGHT_RADIUS: i32 = 20;
+const SCOUT_SIGHT_RADIUS: i32 = 28;
+/// Walking time of the scout relative to others
+const SCOUT_STRIDE: f64 = 0.8;
+
+/// Kind of character chosen before the run
+#[derive(Clone, Copy, PartialEq)]
+pub enum Archetype {
+    /// Strong in melee, carries little
+    Brawler,
+    /// Sees far and walks fast
+    Scout,
+    /// Weaker, but lucky in everything
+    Rogue,
+}
+
+impl Archetype {
+    pub const ALL: [Archetype; 3] = [Self::Brawler, Self::Scout, Self::Rogue];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Brawler => "brawler",
+            Self::Scout => "scout",
+            Self::Rogue => "rogue",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Brawler => "hits hard and takes a beating",
+            Self::Scout => "sees far and walks fast",
+            Self::Rogue => "lucky in fights and at finding traps",
+        }
+    }
+
+    pub fn stats(self) -> CombatStats {
+        match self {
+            Self::Brawler => CombatStats::new(1.5, 1.2, 0.0).with_health(25, 3, 6),
+            Self::Scout => CombatStats::new(1.0, 1.0, 0.0).with_health(18, 2, 5),
+            Self::Rogue => CombatStats::new(0.9, 0.9, 0.3).with_health(18, 2, 4),
+        }
+    }
+
+    pub fn view_radius(self) -> i32 {
+        match self {
+            Self::Scout => SCOUT_SIGHT_RADIUS,
+            _ => SIGHT_RADIUS,
+        }
+    }
+
+    /// Multiplier of the walking time
+    pub fn stride(self) -> f64 {
+        match self {
+            Self::Scout => SCOUT_STRIDE,
+            _ => 1.0,
+        }
+    }
+
+    /// Starting equipment
+    pub fn inventory(self) -> Inventory {
+        let mut inventory = Inventory::default();
+
+        match self {
+            Self::Brawler => inventory.add(ItemKind::ThrowingKnife, 5),
+            Self::Scout => {
+                inventory.add(ItemKind::Bow, 1);
+                inventory.add(ItemKind::Arrow, 20);
+            }
+            Self::Rogue => {
+                inventory.add(ItemKind::Bow, 1);
+                inventory.add(ItemKind::Arrow, 10);
+                inventory.add(ItemKind::ThrowingKnife, 6);
+                inventory.add(ItemKind::OilFlask, 1);
+            }
+        }
+
+        inventory
+    }
+
+    pub fn ability(self) -> Ability {
+        match self {
+            Self::Brawler => Ability::BattleCry,
+            Self::Scout => Ability::Sprint,
+            Self::Rogue => Ability::SmokeBomb,
+        }
+    }
+}
+
+/// Special action of an archetype, it needs time to recover after use
+#[derive(Clone, Copy, PartialEq)]
+pub enum Ability {
+    /// Stuns the enemies next to the player
+    BattleCry,
+    /// Hastes the player for a while
+    Sprint,
+    /// Blinds the enemies around the player
+    SmokeBomb,
+}
+
+impl Ability {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::BattleCry => "battle cry",
+            Self::Sprint => "sprint",
+            Self::SmokeBomb => "smoke bomb",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::BattleCry => "stun the enemies next to you",
+            Self::Sprint => "move twice as fast for a while",
+            Self::SmokeBomb => "blind the enemies around you",
+        }
+    }
+}
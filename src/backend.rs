@@ -0,0 +1,34 @@
+// Where the game is rendered and read input from. The backend is chosen
+// by cargo features: `opengl` opens a window, `curses` runs in a text terminal
+
+use bracket_terminal::prelude::*;
+
+pub type BackendResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const TITLE: &str = "Rogue game";
+
+#[cfg(feature = "opengl")]
+bracket_terminal::embedded_resource!(TILE_FONT, "../resources/vga8x16.png");
+
+/// Graphical window with the bundled font
+#[cfg(feature = "opengl")]
+pub fn build_context(width: u64, height: u64) -> BackendResult<BTerm> {
+    bracket_terminal::link_resource!(TILE_FONT, "resources/vga8x16.png");
+
+    BTermBuilder::new()
+        .with_dimensions(width, height)
+        .with_tile_dimensions(12, 24)
+        .with_title(TITLE)
+        .with_font("vga8x16.png", 8, 16)
+        .with_simple_console(width, height, "vga8x16.png")
+        .build()
+}
+
+/// Text terminal, it works over SSH. The terminal should be
+/// at least as large as the console
+#[cfg(all(feature = "curses", not(feature = "opengl")))]
+pub fn build_context(width: u64, height: u64) -> BackendResult<BTerm> {
+    BTermBuilder::simple(width, height)?
+        .with_title(TITLE)
+        .build()
+}
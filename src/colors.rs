@@ -1,43 +1,311 @@
+use crate::config::{read_entries, ConfigError, ConfigResult};
+use bracket_terminal::prelude::RGB;
 pub use bracket_terminal::prelude::RGBA;
+use std::cell::Cell;
+use std::path::Path;
 
-pub const COLOR_WALL: RGBA = RGBA {
-    r: 0.5333,
-    g: 0.4,
-    b: 0.5333,
-    a: 1.0,
-};
+/// Declares the palette and lookup of its colors by names used in palette files
+macro_rules! palette {
+    ($($name:ident),* $(,)?) => {
+        #[derive(Clone, Copy)]
+        pub struct Palette {
+            $(pub $name: RGBA,)*
+        }
 
-pub const COLOR_EMPTY: RGBA = RGBA {
-    r: 0.5,
-    g: 0.5,
-    b: 0.5,
-    a: 1.0,
-};
+        impl Palette {
+            fn color_mut(&mut self, name: &str) -> Option<&mut RGBA> {
+                match name {
+                    $(stringify!($name) => Some(&mut self.$name),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
 
-pub const COLOR_BG: RGBA = RGBA {
-    r: 0.0,
-    g: 0.0,
-    b: 0.0,
-    a: 0.0,
-};
+palette!(
+    wall,
+    empty,
+    remembered,
+    cursor,
+    target_line,
+    bg,
+    player,
+    rat,
+    goblin,
+    troll,
+    rock_worm,
+    blood,
+    item,
+    log,
+    log_old,
+    torch,
+    glowing_moss,
+    player_light,
+    dried_blood,
+    remembered_blood,
+    spark,
+    spark_faded,
+    dust,
+    dust_faded,
+    smoke,
+    smoke_faded,
+    magic,
+    magic_faded,
+    hit_flash,
+    door,
+    locked_door,
+    water,
+    water_bg,
+    deep_water,
+    deep_water_bg,
+    lava,
+    lava_bg,
+    chasm,
+    rubble,
+    glass,
+    iron_bars,
+    fungus,
+    trap,
+);
+
+/// Color taken from the current palette when drawn, so it follows theme changes
+pub type PaletteColor = fn(&Palette) -> RGBA;
 
-pub const COLOR_PLAYER: RGBA = RGBA {
-    r: 1.0,
-    g: 1.0,
-    b: 1.0,
-    a: 1.0,
+const fn rgb(r: f32, g: f32, b: f32) -> RGBA {
+    RGBA { r, g, b, a: 1.0 }
+}
+
+const DEFAULT: Palette = Palette {
+    wall: rgb(0.5333, 0.4, 0.5333),
+    empty: rgb(0.5, 0.5, 0.5),
+    remembered: rgb(0.2, 0.2, 0.3),
+    cursor: rgb(0.3, 0.3, 0.6),
+    target_line: rgb(0.2, 0.2, 0.35),
+    bg: RGBA {
+        r: 0.0,
+        g: 0.0,
+        b: 0.0,
+        a: 0.0,
+    },
+    player: rgb(1.0, 1.0, 1.0),
+    rat: rgb(0.4, 0.4, 0.4667),
+    goblin: rgb(0.2, 1.0, 0.2),
+    troll: rgb(0.2667, 0.9333, 0.9333),
+    rock_worm: rgb(0.8, 0.6, 0.4),
+    blood: rgb(0.9333, 0.2, 0.2),
+    item: rgb(0.9, 0.8, 0.4),
+    log: rgb(0.9, 0.9, 0.9),
+    log_old: rgb(0.5, 0.5, 0.5),
+    torch: rgb(1.0, 0.7, 0.4),
+    glowing_moss: rgb(0.4, 1.0, 0.6),
+    player_light: rgb(1.0, 0.85, 0.6),
+    dried_blood: rgb(0.35, 0.1, 0.1),
+    remembered_blood: rgb(0.3, 0.15, 0.25),
+    spark: rgb(1.0, 0.9, 0.5),
+    spark_faded: rgb(0.8, 0.3, 0.1),
+    dust: rgb(0.6, 0.55, 0.45),
+    dust_faded: rgb(0.3, 0.28, 0.25),
+    smoke: rgb(0.6, 0.6, 0.6),
+    smoke_faded: rgb(0.25, 0.25, 0.25),
+    magic: rgb(0.5, 1.0, 0.9),
+    magic_faded: rgb(0.5, 0.3, 0.9),
+    hit_flash: rgb(0.8, 0.1, 0.1),
+    door: rgb(0.7, 0.45, 0.2),
+    locked_door: rgb(0.9, 0.75, 0.3),
+    water: rgb(0.4, 0.6, 1.0),
+    water_bg: rgb(0.0, 0.08, 0.25),
+    deep_water: rgb(0.2, 0.35, 0.9),
+    deep_water_bg: rgb(0.0, 0.04, 0.18),
+    lava: rgb(1.0, 0.6, 0.1),
+    lava_bg: rgb(0.45, 0.05, 0.0),
+    chasm: rgb(0.2, 0.15, 0.25),
+    rubble: rgb(0.6, 0.55, 0.5),
+    glass: rgb(0.6, 0.85, 0.9),
+    iron_bars: rgb(0.5, 0.55, 0.65),
+    fungus: rgb(0.7, 0.4, 0.8),
+    trap: rgb(0.9, 0.3, 0.6),
 };
 
-pub const COLOR_RAT: RGBA = RGBA {
-    r: 0.4,
-    g: 0.4,
-    b: 0.4667,
-    a: 1.0,
+const HIGH_CONTRAST: Palette = Palette {
+    wall: rgb(1.0, 1.0, 1.0),
+    empty: rgb(0.75, 0.75, 0.75),
+    remembered: rgb(0.35, 0.35, 0.6),
+    cursor: rgb(0.2, 0.2, 0.9),
+    target_line: rgb(0.1, 0.1, 0.5),
+    player: rgb(0.0, 1.0, 1.0),
+    rat: rgb(1.0, 0.3, 1.0),
+    goblin: rgb(0.0, 1.0, 0.0),
+    troll: rgb(1.0, 0.6, 0.0),
+    rock_worm: rgb(1.0, 1.0, 0.5),
+    blood: rgb(1.0, 0.0, 0.0),
+    item: rgb(1.0, 1.0, 0.0),
+    log: rgb(1.0, 1.0, 1.0),
+    log_old: rgb(0.7, 0.7, 0.7),
+    dried_blood: rgb(0.6, 0.0, 0.0),
+    remembered_blood: rgb(0.6, 0.2, 0.4),
+    hit_flash: rgb(1.0, 0.0, 0.0),
+    door: rgb(1.0, 0.6, 0.0),
+    locked_door: rgb(1.0, 1.0, 0.0),
+    water: rgb(0.3, 0.6, 1.0),
+    lava: rgb(1.0, 0.4, 0.0),
+    chasm: rgb(0.5, 0.5, 0.5),
+    rubble: rgb(0.9, 0.9, 0.6),
+    glass: rgb(0.5, 1.0, 1.0),
+    fungus: rgb(1.0, 0.5, 1.0),
+    trap: rgb(1.0, 0.0, 0.5),
+    ..DEFAULT
 };
 
-pub const COLOR_BLOOD: RGBA = RGBA {
-    r: 0.9333,
-    g: 0.2,
-    b: 0.2,
-    a: 1.0,
+/// Colors distinguishable with color vision deficiency, based on the Okabe-Ito palette
+const COLORBLIND: Palette = Palette {
+    rat: rgb(0.8, 0.47, 0.65),
+    goblin: rgb(0.0, 0.62, 0.45),
+    troll: rgb(0.34, 0.71, 0.91),
+    rock_worm: rgb(0.9, 0.6, 0.0),
+    blood: rgb(0.84, 0.37, 0.0),
+    item: rgb(0.94, 0.89, 0.26),
+    torch: rgb(0.9, 0.6, 0.0),
+    glowing_moss: rgb(0.34, 0.71, 0.91),
+    dried_blood: rgb(0.45, 0.2, 0.0),
+    remembered_blood: rgb(0.4, 0.25, 0.1),
+    spark: rgb(0.94, 0.89, 0.26),
+    spark_faded: rgb(0.9, 0.6, 0.0),
+    magic: rgb(0.34, 0.71, 0.91),
+    magic_faded: rgb(0.0, 0.45, 0.7),
+    hit_flash: rgb(0.84, 0.37, 0.0),
+    door: rgb(0.9, 0.6, 0.0),
+    locked_door: rgb(0.94, 0.89, 0.26),
+    water: rgb(0.34, 0.71, 0.91),
+    deep_water: rgb(0.0, 0.45, 0.7),
+    lava: rgb(0.84, 0.37, 0.0),
+    fungus: rgb(0.8, 0.47, 0.65),
+    trap: rgb(0.9, 0.6, 0.0),
+    ..DEFAULT
 };
+
+thread_local! {
+    static PALETTE: Cell<Palette> = const { Cell::new(DEFAULT) };
+}
+
+/// Colors of the current theme
+pub fn palette() -> Palette {
+    PALETTE.with(|p| p.get())
+}
+
+fn set_palette(palette: Palette) {
+    PALETTE.with(|p| p.set(palette));
+}
+
+#[derive(Clone)]
+pub struct Theme {
+    pub name: String,
+    palette: Palette,
+}
+
+impl Theme {
+    fn new(name: &str, palette: Palette) -> Self {
+        Self {
+            name: name.to_string(),
+            palette,
+        }
+    }
+
+    /// Load a palette file of `color = #rrggbb` lines. Colors which are not set
+    /// are taken from the theme chosen by `base = name`, or from the default one.
+    /// The base comes first, as it replaces the whole palette
+    fn load(path: &Path, themes: &[Theme]) -> ConfigResult<Theme> {
+        let entries = match read_entries(path)? {
+            Some(entries) => entries,
+            None => return Err(format!("{}: theme not found", path.display()).into()),
+        };
+
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut palette = DEFAULT;
+        let mut has_base = false;
+        let mut has_colors = false;
+
+        for entry in entries {
+            if entry.key == "base" {
+                if has_base || has_colors {
+                    let message = "`base` must be set once, before any color".to_string();
+                    return Err(ConfigError::new(path, entry.line, message).into());
+                }
+                has_base = true;
+
+                palette = match themes.iter().find(|t| t.name == entry.value) {
+                    Some(theme) => theme.palette,
+                    None => {
+                        let message = format!("unknown theme `{}`", entry.value);
+                        return Err(ConfigError::new(path, entry.line, message).into());
+                    }
+                };
+                continue;
+            }
+
+            has_colors = true;
+
+            let color = RGB::from_hex(&entry.value).map_err(|_| {
+                let message = format!("invalid color `{}`, expected `#rrggbb`", entry.value);
+                ConfigError::new(path, entry.line, message)
+            })?;
+
+            match palette.color_mut(&entry.key) {
+                Some(c) => *c = color.into(),
+                None => {
+                    let message = format!("unknown color `{}`", entry.key);
+                    return Err(ConfigError::new(path, entry.line, message).into());
+                }
+            }
+        }
+
+        Ok(Theme::new(&name, palette))
+    }
+}
+
+/// Available themes, the current one sets the palette
+#[derive(Clone)]
+pub struct Themes {
+    themes: Vec<Theme>,
+    current: usize,
+}
+
+impl Themes {
+    /// Built-in themes and the selected one, which is either
+    /// a name of a built-in theme or a path to a palette file
+    pub fn load(selected: &str) -> ConfigResult<Themes> {
+        let mut themes = vec![
+            Theme::new("default", DEFAULT),
+            Theme::new("high_contrast", HIGH_CONTRAST),
+            Theme::new("colorblind", COLORBLIND),
+        ];
+
+        let current = match themes.iter().position(|t| t.name == selected) {
+            Some(i) => i,
+            None => {
+                let theme = Theme::load(Path::new(selected), &themes)?;
+                themes.push(theme);
+                themes.len() - 1
+            }
+        };
+
+        let themes = Themes { themes, current };
+        set_palette(themes.current().palette);
+
+        Ok(themes)
+    }
+
+    pub fn current(&self) -> &Theme {
+        &self.themes[self.current]
+    }
+
+    /// Switch to the next theme
+    pub fn next(&mut self) -> &Theme {
+        self.current = (self.current + 1) % self.themes.len();
+        set_palette(self.current().palette);
+        self.current()
+    }
+}
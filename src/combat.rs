@@ -0,0 +1,220 @@
+use crate::status::{Affliction, StatusKind};
+use rand::Rng;
+use std::str::FromStr;
+
+/// Probability to hit a target next to the shooter
+const BASE_HIT_PROB: f64 = 0.95;
+/// Decrease of the hit probability per cell of distance
+const HIT_PROB_PER_DISTANCE: f64 = 0.04;
+const MIN_HIT_PROB: f64 = 0.05;
+/// Turns to heal completely from the brink of death
+const REGEN_TURNS: f64 = 150.0;
+
+/// How blows are resolved
+#[derive(Clone, Copy, PartialEq)]
+pub enum CombatRules {
+    /// Every blow may kill with a probability from attack and defence
+    Chance,
+    /// Blows deal damage and kill when health points run out
+    Health,
+}
+
+impl FromStr for CombatRules {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chance" => Ok(Self::Chance),
+            "health" => Ok(Self::Health),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Range of health points taken by a blow
+#[derive(Clone, Copy)]
+pub struct Damage {
+    pub min: u32,
+    pub max: u32,
+}
+
+impl Damage {
+    pub fn roll(self) -> u32 {
+        rand::thread_rng().gen_range(self.min, self.max + 1)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct CombatStats {
+    pub attack: f64,
+    pub defence: f64,
+    pub luck: f64,
+    pub max_hp: u32,
+    pub damage: Damage,
+    pub affliction: Option<Affliction>,
+}
+
+impl CombatStats {
+    pub fn new(attack: f64, defence: f64, luck: f64) -> Self {
+        Self {
+            attack,
+            defence,
+            luck,
+            max_hp: 1,
+            damage: Damage { min: 1, max: 1 },
+            affliction: None,
+        }
+    }
+
+    /// Health points and damage used by the health rules
+    pub fn with_health(self, max_hp: u32, min_damage: u32, max_damage: u32) -> Self {
+        Self {
+            max_hp,
+            damage: Damage {
+                min: min_damage,
+                max: max_damage,
+            },
+            ..self
+        }
+    }
+
+    /// Status the blows may leave on the target
+    pub fn with_affliction(self, kind: StatusKind, prob: f64, duration: f64) -> Self {
+        Self {
+            affliction: Some(Affliction {
+                kind,
+                prob,
+                duration,
+            }),
+            ..self
+        }
+    }
+
+    /// Probability to kill the enemy with a single blow
+    pub fn kill_prob(&self, enemy: &CombatStats) -> f64 {
+        self.attack * (1.0 + self.luck) / enemy.defence / (1.0 + enemy.luck)
+    }
+
+    /// Probability to hit the enemy with a projectile from the distance
+    pub fn hit_prob(&self, enemy: &CombatStats, distance: f64) -> f64 {
+        let prob = (BASE_HIT_PROB - HIT_PROB_PER_DISTANCE * distance) * (1.0 + self.luck)
+            / (1.0 + enemy.luck);
+
+        prob.clamp(MIN_HIT_PROB, BASE_HIT_PROB)
+    }
+
+    /// Stats of a blow made with a weapon of the given power
+    pub fn with_power(&self, power: f64) -> CombatStats {
+        CombatStats {
+            attack: self.attack * power,
+            damage: Damage {
+                min: (self.damage.min as f64 * power).round() as u32,
+                max: (self.damage.max as f64 * power).round() as u32,
+            },
+            ..*self
+        }
+    }
+}
+
+/// Health points of a creature, they regenerate with time
+#[derive(Clone, Copy)]
+pub struct Health {
+    hp: f64,
+    max_hp: f64,
+}
+
+impl Health {
+    pub fn new(max_hp: u32) -> Self {
+        Self {
+            hp: max_hp as f64,
+            max_hp: max_hp as f64,
+        }
+    }
+
+    pub fn hp(&self) -> u32 {
+        self.hp.ceil() as u32
+    }
+
+    pub fn max_hp(&self) -> u32 {
+        self.max_hp as u32
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.hp >= self.max_hp
+    }
+
+    /// Share of health points left, from 0 to 1
+    pub fn share(&self) -> f64 {
+        (self.hp / self.max_hp).clamp(0.0, 1.0)
+    }
+
+    pub fn wound(&mut self, damage: u32) {
+        self.drain(damage as f64);
+    }
+
+    /// Lose health points bit by bit, like from poison
+    pub fn drain(&mut self, hp: f64) {
+        self.hp = f64::max(self.hp - hp, 0.0);
+    }
+
+    /// Raise the maximum, the new health points come at once
+    pub fn raise(&mut self, hp: u32) {
+        self.max_hp += hp as f64;
+        self.hp += hp as f64;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hp <= 0.0
+    }
+
+    pub fn regenerate(&mut self, time: f64) {
+        self.hp = f64::min(self.hp + self.max_hp * time / REGEN_TURNS, self.max_hp);
+    }
+
+    /// How hurt the creature looks
+    pub fn apparent(&self) -> &'static str {
+        match self.share() {
+            s if s >= 1.0 => "unhurt",
+            s if s >= 0.75 => "scratched",
+            s if s >= 0.5 => "wounded",
+            s if s >= 0.25 => "badly wounded",
+            _ => "almost dead",
+        }
+    }
+}
+
+/// Outcome of a blow which has reached the target
+pub struct Blow {
+    pub kill: bool,
+    pub damage: u32,
+    /// Status left on the surviving target
+    pub affliction: Option<Affliction>,
+}
+
+impl Blow {
+    /// Roll the blow of the attacker by the rules
+    pub fn roll(
+        rules: CombatRules,
+        attacker: &CombatStats,
+        target: &CombatStats,
+        health: &Health,
+    ) -> Self {
+        let (kill, damage) = match rules {
+            CombatRules::Chance => (roll(attacker.kill_prob(target)), 0),
+            CombatRules::Health => {
+                let damage = attacker.damage.roll();
+                (damage >= health.hp(), damage)
+            }
+        };
+
+        Self {
+            kill,
+            damage,
+            affliction: attacker.affliction.filter(|a| !kill && roll(a.prob)),
+        }
+    }
+}
+
+pub fn roll(prob: f64) -> bool {
+    rand::random::<f64>() < prob
+}
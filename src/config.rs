@@ -0,0 +1,74 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub type ConfigResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+#[derive(Debug)]
+pub struct ConfigError {
+    path: PathBuf,
+    line: usize,
+    message: String,
+}
+
+impl ConfigError {
+    pub fn new(path: &Path, line: usize, message: String) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            line,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.path.display(), self.line, self.message)
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Single `key = value` line of a config file
+pub struct ConfigEntry {
+    pub line: usize,
+    pub key: String,
+    pub value: String,
+}
+
+/// Read `key = value` entries from a config file.
+/// Empty lines and lines starting with `#` are skipped.
+/// Returns `None` if the file does not exist
+pub fn read_entries(path: &Path) -> ConfigResult<Option<Vec<ConfigEntry>>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut entries = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match line.find('=') {
+            Some(at) => entries.push(ConfigEntry {
+                line: i + 1,
+                key: line[..at].trim().to_string(),
+                value: line[at + 1..].trim().to_string(),
+            }),
+            None => {
+                let message = format!("expected `key = value`, found `{}`", line);
+                return Err(ConfigError::new(path, i + 1, message).into());
+            }
+        }
+    }
+
+    Ok(Some(entries))
+}
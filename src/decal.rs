@@ -0,0 +1,57 @@
+use crate::colors::{palette, RGBA};
+
+/// Turns needed for fresh blood to dry completely
+const DRYING_TURNS: f64 = 200.0;
+
+#[derive(Clone, Copy)]
+pub enum DecalKind {
+    Blood,
+}
+
+/// Persistent mark left on a cell
+#[derive(Clone, Copy)]
+pub struct Decal {
+    pub kind: DecalKind,
+    /// Turns passed since the mark was left
+    age: f64,
+}
+
+impl Decal {
+    pub fn new(kind: DecalKind) -> Self {
+        Self { kind, age: 0.0 }
+    }
+
+    pub fn grow_old(&mut self, turns: f64) {
+        self.age += turns;
+    }
+
+    /// 0 for a fresh mark, 1 for a completely dried one
+    fn dryness(&self) -> f32 {
+        f64::min(self.age / DRYING_TURNS, 1.0) as f32
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            DecalKind::Blood if self.dryness() < 0.5 => "pool of blood",
+            DecalKind::Blood => "dried blood",
+        }
+    }
+
+    pub fn glyph(&self) -> &'static str {
+        match self.kind {
+            DecalKind::Blood => "░",
+        }
+    }
+
+    pub fn color(&self) -> RGBA {
+        match self.kind {
+            DecalKind::Blood => palette().blood.lerp(palette().dried_blood, self.dryness()),
+        }
+    }
+
+    pub fn remembered_color(&self) -> RGBA {
+        match self.kind {
+            DecalKind::Blood => palette().remembered_blood,
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::draw::Point;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Self::North,
+        Self::NorthEast,
+        Self::East,
+        Self::SouthEast,
+        Self::South,
+        Self::SouthWest,
+        Self::West,
+        Self::NorthWest,
+    ];
+
+    /// Offset of a neighbour cell in this direction
+    pub fn delta(self) -> Point {
+        match self {
+            Self::North => Point::new(0, -1),
+            Self::NorthEast => Point::new(1, -1),
+            Self::East => Point::new(1, 0),
+            Self::SouthEast => Point::new(1, 1),
+            Self::South => Point::new(0, 1),
+            Self::SouthWest => Point::new(-1, 1),
+            Self::West => Point::new(-1, 0),
+            Self::NorthWest => Point::new(-1, -1),
+        }
+    }
+
+    /// Direction to a neighbour cell
+    pub fn from_delta(delta: Point) -> Option<Direction> {
+        Self::ALL.iter().copied().find(|d| d.delta() == delta)
+    }
+
+    /// Unit vector pointing in this direction
+    pub fn unit(self) -> (f64, f64) {
+        let delta = self.delta();
+        let (x, y) = (delta.x as f64, delta.y as f64);
+        let l = (x * x + y * y).sqrt();
+        (x / l, y / l)
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        let delta = self.delta();
+        delta.x != 0 && delta.y != 0
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::North => "north",
+            Self::NorthEast => "north_east",
+            Self::East => "east",
+            Self::SouthEast => "south_east",
+            Self::South => "south",
+            Self::SouthWest => "south_west",
+            Self::West => "west",
+            Self::NorthWest => "north_west",
+        }
+    }
+}
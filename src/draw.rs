@@ -1,3 +1,4 @@
+use crate::field::Field;
 pub use bracket_pathfinding::prelude::Point;
 pub use bracket_terminal::prelude::BTerm;
 use std::collections::HashSet;
@@ -11,3 +12,8 @@ pub trait Draw {
 pub trait DrawWithFov {
     fn draw_with_fov(&self, ctx: &mut BTerm, fov: &Fov, pos: Point, fov_pos: Point);
 }
+
+/// Something standing on the field, it looks dim away from the light
+pub trait DrawLit {
+    fn draw_lit(&self, ctx: &mut BTerm, fov: &Fov, field: &Field, pos: Point);
+}
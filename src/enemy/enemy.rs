@@ -1,19 +1,79 @@
-use crate::colors::{COLOR_BG, RGBA};
-use crate::draw::{BTerm, DrawWithFov, Fov, Point};
-use crate::field::FieldPosition;
+use crate::action::Action;
+use crate::colors::{palette, PaletteColor, RGBA};
+use crate::combat::{Blow, CombatRules, CombatStats, Health};
+use crate::direction::Direction;
+use crate::draw::{BTerm, DrawLit, Fov, Point};
+use crate::field::{Field, FieldPosition};
+use crate::particles::ParticleKind;
 use crate::state::{State, Stepper, StepperStatus};
+use crate::status::{StatusKind, Statuses};
+use crate::terrain::FieldCell;
+use bracket_pathfinding::prelude::DistanceAlg;
 
 const SPOT_DISTANCE: f64 = 8.0;
-const ATTACK_DISTANCE: f64 = 1.0;
+/// Blind enemies notice only the player next to them
+const BLIND_SPOT_DISTANCE: f64 = 1.5;
+/// Attack strength which makes a usual blood splash, stronger blows splash more
+const SPLASH_ATTACK: f64 = 0.03;
+const MAX_SPLASH_POWER: f64 = 3.0;
+/// Minimal share of the move intent along an axis to make a step along it,
+/// sin(22.5°) splits all directions evenly between eight neighbours
+const AXIS_STEP_THRESHOLD: f64 = 0.3827;
+/// Color share of blood on an enemy about to die
+const WOUND_TINT: f32 = 0.7;
+/// Turns the enemy waits after an attack before the next one
+const ATTACK_COOLDOWN: i32 = 2;
+
+/// What the enemy is doing, as seen by the player
+#[derive(Clone, Copy)]
+pub enum Behaviour {
+    Unaware,
+    Hunting,
+    Wandering,
+    /// Going to the place of an alarm
+    Alerted,
+}
+
+impl Behaviour {
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Unaware => "unaware of you",
+            Self::Hunting => "hunting you",
+            Self::Wandering => "wandering around",
+            Self::Alerted => "alerted by a noise",
+        }
+    }
+}
+
+/// How the enemy gets around obstacles
+#[derive(Clone, Copy, PartialEq)]
+pub enum Movement {
+    Walk,
+    /// Closed doors do not stop the enemy
+    OpenDoors,
+    /// The enemy tunnels through walls, leaving floor behind
+    Burrow,
+}
 
 pub struct Enemy {
+    pub name: &'static str,
     draw_char: char,
-    draw_color: RGBA,
+    draw_color: PaletteColor,
     action_time: f64,
     pos: Point,
     clock: f64,
     move_intent: (f64, f64),
     staying_steps: i32,
+    /// Turns left until the enemy may attack again
+    attack_cooldown: i32,
+    pub behaviour: Behaviour,
+    pub stats: CombatStats,
+    pub health: Health,
+    pub statuses: Statuses,
+    movement: Movement,
+    /// Place of the alarm the enemy goes to
+    alarm: Option<Point>,
+    dead: bool,
 }
 
 fn normalize(x: f64, y: f64) -> (f64, f64) {
@@ -22,8 +82,17 @@ fn normalize(x: f64, y: f64) -> (f64, f64) {
 }
 
 impl Enemy {
-    pub fn new(draw_char: char, draw_color: RGBA, action_time: f64, pos: Point) -> Self {
+    pub fn new(
+        name: &'static str,
+        draw_char: char,
+        draw_color: PaletteColor,
+        action_time: f64,
+        pos: Point,
+        stats: CombatStats,
+        movement: Movement,
+    ) -> Self {
         Self {
+            name,
             clock: 0.0,
             pos,
             draw_char,
@@ -31,9 +100,84 @@ impl Enemy {
             action_time,
             move_intent: (0.0, 0.0),
             staying_steps: 0,
+            attack_cooldown: 0,
+            behaviour: Behaviour::Unaware,
+            stats,
+            health: Health::new(stats.max_hp),
+            statuses: Statuses::default(),
+            movement,
+            alarm: None,
+            dead: false,
+        }
+    }
+
+    pub fn kill(&mut self) {
+        self.dead = true;
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.dead
+    }
+
+    pub fn teleport(&mut self, pos: Point) {
+        self.pos = pos;
+    }
+
+    /// Lose time, like when caught in a net
+    pub fn delay(&mut self, time: f64) {
+        self.clock += time;
+    }
+
+    /// Go to the place of the alarm
+    pub fn alert(&mut self, pos: Point) {
+        self.alarm = Some(pos);
+    }
+
+    /// Let the time of the action pass, enemies carry nothing to slow them down
+    fn spend(&mut self, action: Action) {
+        self.clock += self.action_time * action.time(&self.statuses, 1.0);
+    }
+
+    /// Stats weakened by the statuses
+    pub fn combat_stats(&self) -> CombatStats {
+        self.statuses.apply(self.stats)
+    }
+
+    /// Let the statuses wear off, poison and bleeding may kill under the health rules
+    fn tick_statuses(&mut self, world: &State, time: f64) {
+        let drain = self.statuses.tick(time);
+
+        if world.rules != CombatRules::Health || self.is_dead() {
+            return;
+        }
+
+        self.health.drain(drain);
+
+        if self.health.is_empty() {
+            self.kill();
+
+            if world.can_see(self.pos) {
+                let (glyph, color) = self.appearance();
+                world
+                    .animations
+                    .borrow_mut()
+                    .death_fade(&world.fov, self.pos, glyph, color);
+                world
+                    .log
+                    .borrow_mut()
+                    .add(format!("The {} dies.", self.name));
+            }
         }
     }
 
+    /// Glyph and color, wounded enemies look bloody
+    pub fn appearance(&self) -> (char, RGBA) {
+        let color = (self.draw_color)(&palette());
+        let wounds = (1.0 - self.health.share()) as f32 * WOUND_TINT;
+
+        (self.draw_char, color.lerp(palette().blood, wounds))
+    }
+
     fn wonder_intent(&self) -> f64 {
         f64::max(
             (2.17_f64).powf(2.0 * (self.staying_steps as f64)) - 1.0,
@@ -56,37 +200,202 @@ impl Enemy {
         self.move_intent.1 += intent * (rand::random::<f64>() - 0.5);
     }
 
+    /// Step in the direction closest to the move intent.
+    /// If a diagonal step is blocked try to step along the main axis of the intent
     fn action_move(&mut self, world: &State) {
         self.move_intent = normalize(self.move_intent.0, self.move_intent.1);
-        let mut next = self.pos;
+        let (ix, iy) = self.move_intent;
 
-        if self.move_intent.0.abs() >= self.move_intent.1.abs() {
-            next.x += self.move_intent.0.signum() as i32;
-            self.move_intent.0 -= self.move_intent.0.signum();
-        } else {
-            next.y += self.move_intent.1.signum() as i32;
-            self.move_intent.1 -= self.move_intent.1.signum();
+        let axis_step = |v: f64| {
+            if v.abs() >= AXIS_STEP_THRESHOLD {
+                v.signum() as i32
+            } else {
+                0
+            }
+        };
+
+        let mut steps = vec![Point::new(axis_step(ix), axis_step(iy))];
+
+        if steps[0].x != 0 && steps[0].y != 0 {
+            if ix.abs() >= iy.abs() {
+                steps.push(Point::new(steps[0].x, 0));
+            } else {
+                steps.push(Point::new(0, steps[0].y));
+            }
+        }
+
+        let direction = steps
+            .into_iter()
+            .filter_map(Direction::from_delta)
+            .find(|d| self.can_move(world, *d) || self.can_open(world, *d));
+
+        match direction {
+            Some(direction) if self.can_open(world, direction) => {
+                let door = self.pos + direction.delta();
+                world.change_terrain(door, FieldCell::OpenDoor);
+                self.spend(Action::Door);
+
+                if world.can_see(door) {
+                    world
+                        .log
+                        .borrow_mut()
+                        .add(format!("The {} opens the door.", self.name));
+                }
+            }
+            Some(direction) => {
+                let delta = direction.delta();
+                self.pos = self.pos + delta;
+
+                if world.field.is_diggable(self.pos) {
+                    world.change_terrain(self.pos, FieldCell::Empty);
+                    self.spend(Action::Burrow);
+                } else {
+                    self.spend(Action::Move(world.field.move_cost(self.pos)));
+                }
+
+                if world.field.trap_at(self.pos).is_some() {
+                    world.trigger_trap(self.pos);
+                }
+
+                self.move_intent.0 -= delta.x as f64;
+                self.move_intent.1 -= delta.y as f64;
+                self.staying_steps = i32::max(self.staying_steps - 1, 0);
+            }
+            None => {
+                self.staying_steps += 1;
+                self.spend(Action::Rest);
+            }
+        }
+    }
+
+    fn action_attack(&self, world: &State, direction: Direction) {
+        let target = self.pos + direction.delta();
+
+        let splash = self.stats.attack / SPLASH_ATTACK;
+        world.particles.borrow_mut().spawn(
+            ParticleKind::Blood,
+            target,
+            direction.unit(),
+            (2.0 * splash).ceil() as usize,
+            0.6 * splash.min(MAX_SPLASH_POWER),
+        );
+
+        let mut animations = world.animations.borrow_mut();
+        let (glyph, color) = self.appearance();
+        animations.lunge(&world.fov, self.pos, target, glyph, color);
+        animations.hit_flash(&world.fov, target);
+
+        let mut player = world.player.borrow_mut();
+
+        let blow = Blow::roll(
+            world.rules,
+            &self.combat_stats(),
+            &player.combat_stats(),
+            &player.health,
+        );
+
+        if blow.kill {
+            player.kill();
+            world
+                .log
+                .borrow_mut()
+                .add(format!("The {} kills you.", self.name));
+        } else if blow.damage > 0 {
+            player.health.wound(blow.damage);
+            world
+                .log
+                .borrow_mut()
+                .add(format!("The {} hits you.", self.name));
         }
 
-        if self.can_move(world, &next) {
-            self.pos = next;
-            self.staying_steps = i32::max(self.staying_steps - 1, 0);
+        if let Some(affliction) = blow.affliction {
+            player.statuses.add(affliction.kind, affliction.duration);
+            world
+                .log
+                .borrow_mut()
+                .add(format!("You are {}.", affliction.kind.adjective()));
+        }
+    }
+
+    /// Attack the player next to the enemy, hunt the player nearby,
+    /// go to an alarm or stay unaware
+    fn process_turn(&mut self, world: &State) -> StepperStatus {
+        self.attack_cooldown = i32::max(self.attack_cooldown - 1, 0);
+
+        let delta = world.player.borrow().pos() - self.pos;
+        let distance_squared = delta.x.pow(2) + delta.y.pow(2);
+        let distance = (distance_squared as f64).sqrt();
+        let spot_distance = if self.statuses.has(StatusKind::Blindness) {
+            BLIND_SPOT_DISTANCE
+        } else {
+            SPOT_DISTANCE
+        };
+
+        let attack_direction =
+            Direction::from_delta(delta).filter(|d| world.field.can_step(self.pos, *d));
+
+        if let Some(direction) = attack_direction {
+            self.behaviour = Behaviour::Hunting;
+
+            if self.attack_cooldown > 0 {
+                self.spend(Action::Rest);
+            } else {
+                self.action_attack(world, direction);
+                self.attack_cooldown = ATTACK_COOLDOWN;
+                self.spend(Action::Attack);
+            }
+
+            StepperStatus::Finished
+        } else if distance < spot_distance {
+            self.alarm = None;
+            let wonder_intent = self.wonder_intent();
+
+            self.follow(world.player.borrow().pos());
+            self.random_wondering(wonder_intent);
+
+            self.action_move(world);
+
+            self.behaviour = if self.staying_steps > 0 {
+                Behaviour::Wandering
+            } else {
+                Behaviour::Hunting
+            };
+
+            StepperStatus::Finished
+        } else if let Some(alarm) = self.alarm.filter(|p| *p != self.pos) {
+            self.follow(alarm);
+            self.action_move(world);
+            self.behaviour = Behaviour::Alerted;
+
+            if DistanceAlg::Chebyshev.distance2d(self.pos, alarm) <= 1.0 {
+                self.alarm = None;
+            }
+
+            StepperStatus::Finished
         } else {
-            self.staying_steps += 1;
+            self.alarm = None;
+            self.behaviour = Behaviour::Unaware;
+            self.spend(Action::Rest);
+
+            StepperStatus::Finished
         }
     }
 
-    fn can_move(&self, world: &State, target: &Point) -> bool {
-        !world.field.is_wall(target.x, target.y)
-            && world
-                .enemies
-                .iter()
-                .filter(|e| match e.try_borrow() {
-                    Err(_) => false,
-                    Ok(e) => e.pos == *target,
-                })
-                .next()
-                .is_none()
+    fn can_open(&self, world: &State, direction: Direction) -> bool {
+        self.movement == Movement::OpenDoors
+            && world.field.cell(self.pos + direction.delta()) == Some(FieldCell::ClosedDoor)
+    }
+
+    fn can_move(&self, world: &State, direction: Direction) -> bool {
+        let target = self.pos + direction.delta();
+
+        (world.field.can_step(self.pos, direction)
+            || self.movement == Movement::Burrow && world.field.is_diggable(target))
+            && world.player.borrow().pos() != target
+            && !world.enemies.iter().any(|e| match e.try_borrow() {
+                Err(_) => false,
+                Ok(e) => e.pos == target,
+            })
     }
 }
 
@@ -96,10 +405,12 @@ impl FieldPosition for Enemy {
     }
 }
 
-impl DrawWithFov for Enemy {
-    fn draw_with_fov(&self, ctx: &mut BTerm, fov: &Fov, pos: Point, fov_pos: Point) {
-        if fov.contains(&fov_pos) {
-            ctx.print_color(pos.x, pos.y, self.draw_color, COLOR_BG, self.draw_char)
+impl DrawLit for Enemy {
+    fn draw_lit(&self, ctx: &mut BTerm, fov: &Fov, field: &Field, pos: Point) {
+        if fov.contains(&self.pos) {
+            let (glyph, color) = self.appearance();
+            let color = field.tint(color, self.pos);
+            ctx.print_color(pos.x, pos.y, color, palette().bg, glyph)
         }
     }
 }
@@ -110,25 +421,18 @@ impl Stepper for Enemy {
     }
 
     fn process(&mut self, world: &State, _ctx: &BTerm) -> StepperStatus {
-        self.clock += self.action_time;
-
-        let delta = world.player.borrow().pos() - self.pos;
-        let distance_squared = delta.x.pow(2) + delta.y.pow(2);
-        let distance = (distance_squared as f64).sqrt();
+        let clock = self.clock;
 
-        if distance <= ATTACK_DISTANCE {
+        let status = if self.statuses.has(StatusKind::Stun) {
+            self.spend(Action::Rest);
             StepperStatus::Finished
-        } else if distance < SPOT_DISTANCE {
-            let wonder_intent = self.wonder_intent();
-
-            self.follow(world.player.borrow().pos());
-            self.random_wondering(wonder_intent);
+        } else {
+            self.process_turn(world)
+        };
 
-            self.action_move(world);
+        self.health.regenerate(self.clock - clock);
+        self.tick_statuses(world, self.clock - clock);
 
-            StepperStatus::Finished
-        } else {
-            StepperStatus::Finished
-        }
+        status
     }
 }
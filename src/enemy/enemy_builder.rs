@@ -1,21 +1,74 @@
-use crate::colors::{COLOR_RAT, RGBA};
+use crate::colors::PaletteColor;
+use crate::combat::CombatStats;
 use crate::draw::Point;
-use crate::enemy::Enemy;
+use crate::enemy::{Enemy, Movement};
+use crate::status::StatusKind;
 
 pub struct EnemyBuilder {
+    name: &'static str,
     draw_char: char,
-    draw_color: RGBA,
+    draw_color: PaletteColor,
     pos: Point,
     action_time: f64,
+    stats: CombatStats,
+    movement: Movement,
 }
 
 impl EnemyBuilder {
     pub fn rat() -> Self {
         Self {
+            name: "rat",
             draw_char: 'r',
-            draw_color: COLOR_RAT,
+            draw_color: |p| p.rat,
             pos: Point::zero(),
             action_time: 0.5,
+            stats: CombatStats::new(0.01, 1.0, 0.0)
+                .with_health(3, 1, 2)
+                .with_affliction(StatusKind::Poison, 0.3, 10.0),
+            movement: Movement::Walk,
+        }
+    }
+
+    pub fn goblin() -> Self {
+        Self {
+            name: "goblin",
+            draw_char: 'G',
+            draw_color: |p| p.goblin,
+            pos: Point::zero(),
+            action_time: 1.0 / 0.75,
+            stats: CombatStats::new(0.05, 5.0, 0.0)
+                .with_health(8, 1, 4)
+                .with_affliction(StatusKind::Bleeding, 0.3, 8.0),
+            movement: Movement::OpenDoors,
+        }
+    }
+
+    pub fn troll() -> Self {
+        Self {
+            name: "troll",
+            draw_char: 'T',
+            draw_color: |p| p.troll,
+            pos: Point::zero(),
+            action_time: 5.0,
+            stats: CombatStats::new(0.25, 25.0, 0.0)
+                .with_health(30, 4, 9)
+                .with_affliction(StatusKind::Stun, 0.5, 3.0),
+            movement: Movement::OpenDoors,
+        }
+    }
+
+    /// Slow worm tunnelling through rock
+    pub fn rock_worm() -> Self {
+        Self {
+            name: "rock worm",
+            draw_char: 'w',
+            draw_color: |p| p.rock_worm,
+            pos: Point::zero(),
+            action_time: 1.5,
+            stats: CombatStats::new(0.08, 8.0, 0.0)
+                .with_health(12, 2, 5)
+                .with_affliction(StatusKind::Slow, 0.5, 10.0),
+            movement: Movement::Burrow,
         }
     }
 
@@ -25,6 +78,14 @@ impl EnemyBuilder {
     }
 
     pub fn build(self) -> Enemy {
-        Enemy::new(self.draw_char, self.draw_color, self.action_time, self.pos)
+        Enemy::new(
+            self.name,
+            self.draw_char,
+            self.draw_color,
+            self.action_time,
+            self.pos,
+            self.stats,
+            self.movement,
+        )
     }
 }
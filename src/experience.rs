@@ -0,0 +1,87 @@
+use crate::combat::CombatStats;
+
+/// Experience for a kill per point of the enemy stats
+const XP_PER_ATTACK: f64 = 40.0;
+const XP_PER_DEFENCE: f64 = 2.0;
+/// Experience for the second level, every next level needs more
+const LEVEL_XP: u32 = 20;
+
+/// Experience for killing an enemy, dangerous and tough enemies give more
+pub fn kill_experience(stats: &CombatStats) -> u32 {
+    (stats.attack * XP_PER_ATTACK + stats.defence * XP_PER_DEFENCE).round() as u32
+}
+
+/// Experience needed to reach the level
+pub fn level_experience(level: u32) -> u32 {
+    LEVEL_XP * level * (level - 1) / 2
+}
+
+/// Special skill chosen on a level up instead of a stat, every perk is taken once
+#[derive(Clone, Copy, PartialEq)]
+pub enum Perk {
+    /// Hidden traps are found twice as easily
+    KeenEyes,
+    /// More weight is carried without slowing down
+    PackMule,
+    /// The lantern burns fuel half as fast
+    Lamplighter,
+}
+
+impl Perk {
+    pub const ALL: [Perk; 3] = [Self::KeenEyes, Self::PackMule, Self::Lamplighter];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::KeenEyes => "keen eyes",
+            Self::PackMule => "pack mule",
+            Self::Lamplighter => "lamplighter",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::KeenEyes => "find hidden traps twice as easily",
+            Self::PackMule => "carry more without slowing down",
+            Self::Lamplighter => "the lantern burns fuel half as fast",
+        }
+    }
+}
+
+/// Improvement chosen on a level up
+#[derive(Clone, Copy, PartialEq)]
+pub enum Advance {
+    Attack,
+    Defence,
+    Luck,
+    Perk(Perk),
+}
+
+impl Advance {
+    /// Stats and the perks not taken yet
+    pub fn choices(perks: &[Perk]) -> Vec<Advance> {
+        let stats = [Self::Attack, Self::Defence, Self::Luck];
+        let perks = Perk::ALL
+            .iter()
+            .filter(|p| !perks.contains(p))
+            .map(|p| Self::Perk(*p));
+
+        stats.iter().copied().chain(perks).collect()
+    }
+
+    pub fn description(self) -> String {
+        match self {
+            Self::Attack => "Attack: strike harder".to_string(),
+            Self::Defence => "Defence: withstand more blows".to_string(),
+            Self::Luck => "Luck: a bit of everything goes better".to_string(),
+            Self::Perk(perk) => {
+                let name = perk.name();
+                format!(
+                    "{}{}: {}",
+                    name[..1].to_uppercase(),
+                    &name[1..],
+                    perk.description()
+                )
+            }
+        }
+    }
+}
@@ -1,49 +1,54 @@
-use crate::colors::{COLOR_BG, COLOR_EMPTY, COLOR_WALL};
+use crate::colors::{palette, RGBA};
+use crate::decal::{Decal, DecalKind};
+use crate::direction::Direction;
 use crate::draw::{Draw, DrawWithFov, Fov};
+use crate::light::{LightKind, LightMap, LightSource};
+use crate::terrain::{FieldCell, Terrain};
+use crate::trap::{Trap, TrapKind};
 use bracket_pathfinding::prelude::*;
 use bracket_terminal::prelude::BTerm;
 use rand::Rng;
 
-#[derive(Clone, Copy)]
-enum FieldCell {
-    Empty,
-    Wall,
-}
-
-impl FieldCell {
-    fn smoothed(&self, n_neighbour_walls: u32) -> Self {
-        match self {
-            Self::Empty => {
-                if n_neighbour_walls > 4 {
-                    Self::Wall
-                } else {
-                    Self::Empty
-                }
-            }
-            Self::Wall => {
-                if n_neighbour_walls > 4 {
-                    Self::Wall
-                } else {
-                    Self::Empty
-                }
-            }
-        }
-    }
-}
-
-impl Draw for FieldCell {
-    fn draw(&self, ctx: &mut BTerm, pos: Point) {
-        match self {
-            Self::Empty => ctx.print_color(pos.x, pos.y, COLOR_EMPTY, COLOR_BG, "."),
-            Self::Wall => ctx.print_color(pos.x, pos.y, COLOR_WALL, COLOR_BG, "█"),
-        }
-    }
-}
+const EXPLORE_MAX_DEPTH: f32 = 1000.0;
+const N_TORCHES: usize = 6;
+const N_MOSS_PATCHES: usize = 4;
+const MOSS_PATCH_SIZE: usize = 4;
+const N_DOORS: usize = 6;
+/// Inner size of the vault before stretching
+const VAULT_WIDTH: usize = 5;
+const VAULT_HEIGHT: usize = 3;
+/// Places tried for the vault, the one cutting the least of the cave wins
+const VAULT_ATTEMPTS: usize = 50;
+const N_TRAPS: usize = 10;
+const N_POOLS: usize = 3;
+const POOL_SIZE: usize = 24;
+const N_LAVA_POOLS: usize = 1;
+const LAVA_POOL_SIZE: usize = 8;
+const N_CHASMS: usize = 1;
+const CHASM_SIZE: usize = 10;
+const N_RUBBLE: usize = 20;
+const N_GLASS_WALLS: usize = 3;
+const N_IRON_BARS: usize = 3;
+const N_FUNGUS_PATCHES: usize = 3;
+const FUNGUS_PATCH_SIZE: usize = 8;
 
 pub struct Field {
     width: usize,
     height: usize,
     data: Vec<Vec<FieldCell>>,
+    explored: Vec<Vec<bool>>,
+    /// Horizontal stretch of the generated cave
+    x_scale: usize,
+    /// Blood and other marks left on cells
+    decals: Vec<Vec<Option<Decal>>>,
+    /// Light sources placed on the field
+    lights: Vec<LightSource>,
+    light: LightMap,
+    /// Floor of the locked vault
+    vault: Vec<Point>,
+    /// Walls of the vault, they can not be dug through
+    vault_walls: Vec<Point>,
+    traps: Vec<Trap>,
 }
 
 impl Field {
@@ -52,10 +57,364 @@ impl Field {
         Field {
             width,
             height,
-            data: (0..height)
+            data: vec![vec![FieldCell::Empty; width]; height],
+            explored: vec![vec![false; width]; height],
+            x_scale: 1,
+            decals: vec![vec![None; width]; height],
+            lights: Vec::new(),
+            light: LightMap::new(width, height),
+            vault: Vec::new(),
+            vault_walls: Vec::new(),
+            traps: Vec::new(),
+        }
+    }
+
+    /// Place torches next to walls and patches of glowing moss
+    fn place_lights(&mut self) {
+        let mut rng = rand::thread_rng();
+        let cells = self.empty_cells();
+
+        if cells.is_empty() {
+            return;
+        }
+
+        let near_wall: Vec<_> = cells
+            .iter()
+            .copied()
+            .filter(|p| self.count_neighbour_walls(p.x as usize, p.y as usize) > 0)
+            .collect();
+
+        for _ in 0..N_TORCHES {
+            if let Some(p) = near_wall.get(rng.gen_range(0, near_wall.len().max(1))) {
+                self.add_light(LightSource::new(LightKind::Torch, *p));
+            }
+        }
+
+        for _ in 0..N_MOSS_PATCHES {
+            let mut p = cells[rng.gen_range(0, cells.len())];
+
+            for _ in 0..MOSS_PATCH_SIZE {
+                self.add_light(LightSource::new(LightKind::GlowingMoss, p));
+
+                let direction = Direction::ALL[rng.gen_range(0, Direction::ALL.len())];
+                if self.can_step(p, direction) {
+                    p = p + direction.delta();
+                }
+            }
+        }
+    }
+
+    fn add_light(&mut self, light: LightSource) {
+        if !self.lights.iter().any(|l| l.pos == light.pos) {
+            self.lights.push(light);
+        }
+    }
+
+    pub fn light_at(&self, p: Point) -> Option<&LightSource> {
+        self.lights.iter().find(|l| l.pos == p)
+    }
+
+    /// Recalculate light of the field with placed and additional light sources
+    pub fn update_light(&mut self, extra: &[LightSource]) {
+        let mut light = LightMap::new(self.width, self.height);
+
+        for source in self.lights.iter().chain(extra) {
+            light.add(source, self, self.x_scale as i32);
+        }
+
+        self.light = light;
+    }
+
+    pub fn light_level(&self, p: Point) -> f32 {
+        self.light.level(p)
+    }
+
+    /// Color of an object lit by the light of its cell
+    pub fn tint(&self, color: RGBA, p: Point) -> RGBA {
+        self.light.tint(color, p)
+    }
+
+    /// Cells connected by a random walk over the floor from a random floor cell
+    fn blob(&self, size: usize) -> Vec<Point> {
+        let mut rng = rand::thread_rng();
+        let cells = self.empty_cells();
+
+        if cells.is_empty() {
+            return Vec::new();
+        }
+
+        let mut p = cells[rng.gen_range(0, cells.len())];
+        let mut blob = vec![p];
+
+        for _ in 0..size * 2 {
+            if blob.len() >= size {
+                break;
+            }
+
+            let direction = Direction::ALL[rng.gen_range(0, Direction::ALL.len())];
+            let next = p + direction.delta();
+
+            if let Some(FieldCell::Empty) = self.cell(next) {
+                p = next;
+
+                if !blob.contains(&p) {
+                    blob.push(p);
+                }
+            }
+        }
+
+        blob
+    }
+
+    /// Fill random patches of the floor with the cell
+    fn place_blobs(&mut self, cell: FieldCell, number: usize, size: usize) {
+        for _ in 0..number {
+            for p in self.blob(size) {
+                self.set_cell(p, cell);
+            }
+        }
+    }
+
+    /// Pools of shallow water, deep in the middle
+    fn place_pools(&mut self) {
+        for _ in 0..N_POOLS {
+            let pool = self.blob(POOL_SIZE);
+
+            for p in &pool {
+                self.set_cell(*p, FieldCell::ShallowWater);
+            }
+
+            let deep: Vec<_> = pool
                 .into_iter()
-                .map(|_| std::iter::repeat(FieldCell::Empty).take(width).collect())
-                .collect(),
+                .filter(|p| {
+                    Direction::ALL.iter().all(|d| {
+                        matches!(
+                            self.cell(*p + d.delta()),
+                            Some(FieldCell::ShallowWater) | Some(FieldCell::DeepWater)
+                        )
+                    })
+                })
+                .collect();
+
+            for p in deep {
+                self.set_cell(p, FieldCell::DeepWater);
+            }
+        }
+    }
+
+    /// Rubble fallen from walls
+    fn place_rubble(&mut self) {
+        let mut rng = rand::thread_rng();
+        let near_wall: Vec<_> = self
+            .empty_cells()
+            .into_iter()
+            .filter(|p| self.count_neighbour_walls(p.x as usize, p.y as usize) > 0)
+            .collect();
+
+        if near_wall.is_empty() {
+            return;
+        }
+
+        for _ in 0..N_RUBBLE {
+            let p = near_wall[rng.gen_range(0, near_wall.len())];
+            self.set_cell(p, FieldCell::Rubble);
+        }
+    }
+
+    /// Doors in narrow passages between walls, some of them are closed or locked
+    fn place_doors(&mut self) {
+        let mut rng = rand::thread_rng();
+        let is_wall = |p: Point| matches!(self.cell(p), Some(FieldCell::Wall));
+        let is_floor = |p: Point| matches!(self.cell(p), Some(FieldCell::Empty));
+
+        let mut passages: Vec<_> = self
+            .empty_cells()
+            .into_iter()
+            .filter(|p| {
+                let (w, e) = (*p + Point::new(-1, 0), *p + Point::new(1, 0));
+                let (n, s) = (*p + Point::new(0, -1), *p + Point::new(0, 1));
+
+                (is_wall(w) && is_wall(e) && is_floor(n) && is_floor(s))
+                    || (is_wall(n) && is_wall(s) && is_floor(w) && is_floor(e))
+            })
+            .collect();
+
+        for _ in 0..N_DOORS {
+            if passages.is_empty() {
+                return;
+            }
+
+            let p = passages.remove(rng.gen_range(0, passages.len()));
+            let door = if rng.gen::<bool>() {
+                FieldCell::ClosedDoor
+            } else {
+                FieldCell::OpenDoor
+            };
+
+            self.set_cell(p, door);
+            passages.retain(|other| DistanceAlg::Chebyshev.distance2d(p, *other) > 1.0);
+        }
+    }
+
+    /// Cells from the start along the step up to a walkable one,
+    /// none if the border of the field is reached first
+    fn corridor(&self, start: Point, step: Point) -> Option<Vec<Point>> {
+        let mut p = start;
+        let mut cells = Vec::new();
+
+        while !self.is_walkable(p) {
+            if p.x <= 0
+                || p.y <= 0
+                || p.x as usize >= self.width - 1
+                || p.y as usize >= self.height - 1
+            {
+                return None;
+            }
+
+            cells.push(p);
+            p = p + step;
+        }
+
+        Some(cells)
+    }
+
+    /// Walled room with a locked door and a corridor from the door to the cave
+    fn place_vault(&mut self) {
+        let mut rng = rand::thread_rng();
+        let width = VAULT_WIDTH * self.x_scale + 2;
+        let height = VAULT_HEIGHT + 2;
+
+        if width + 2 >= self.width || height + 2 >= self.height {
+            return;
+        }
+
+        let mut best: Option<(usize, Point, Point, Vec<Point>)> = None;
+
+        for _ in 0..VAULT_ATTEMPTS {
+            let corner = Point::new(
+                rng.gen_range(1, self.width - width),
+                rng.gen_range(1, self.height - height),
+            );
+
+            let (door, step) = if rng.gen::<bool>() {
+                (corner + Point::new(width / 2, 0), Point::new(0, -1))
+            } else {
+                (corner + Point::new(width / 2, height - 1), Point::new(0, 1))
+            };
+
+            let corridor = match self.corridor(door + step, step) {
+                Some(corridor) => corridor,
+                None => continue,
+            };
+
+            let cut = (0..height)
+                .flat_map(|y| (0..width).map(move |x| corner + Point::new(x, y)))
+                .filter(|p| self.is_walkable(*p))
+                .count();
+
+            if best.as_ref().is_none_or(|(best_cut, ..)| cut < *best_cut) {
+                best = Some((cut, corner, door, corridor));
+            }
+        }
+
+        let (_, corner, door, corridor) = match best {
+            Some(best) => best,
+            None => return,
+        };
+
+        for y in 0..height {
+            for x in 0..width {
+                let p = corner + Point::new(x, y);
+
+                if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                    self.set_cell(p, FieldCell::Wall);
+                    self.vault_walls.push(p);
+                } else {
+                    self.set_cell(p, FieldCell::Empty);
+                    self.vault.push(p);
+                }
+            }
+        }
+
+        for p in corridor {
+            self.set_cell(p, FieldCell::Empty);
+        }
+
+        self.set_cell(door, FieldCell::LockedDoor);
+    }
+
+    /// Hidden traps of random kinds on the floor
+    fn place_traps(&mut self) {
+        let mut rng = rand::thread_rng();
+        let mut cells = self.empty_cells();
+
+        for _ in 0..N_TRAPS {
+            if cells.is_empty() {
+                return;
+            }
+
+            let p = cells.remove(rng.gen_range(0, cells.len()));
+            let kind = TrapKind::ALL[rng.gen_range(0, TrapKind::ALL.len())];
+            self.traps.push(Trap::new(kind, p));
+        }
+    }
+
+    pub fn trap_at(&self, p: Point) -> Option<&Trap> {
+        self.traps.iter().find(|t| t.pos == p)
+    }
+
+    /// Hidden traps in the radius around the point
+    pub fn hidden_traps(&self, center: Point, radius: f32) -> Vec<&Trap> {
+        self.traps
+            .iter()
+            .filter(|t| {
+                t.is_hidden() && DistanceAlg::Pythagoras.distance2d(center, t.pos) <= radius
+            })
+            .collect()
+    }
+
+    fn is_known_trap(&self, p: Point) -> bool {
+        self.trap_at(p).is_some_and(|t| !t.is_hidden())
+    }
+
+    /// Floor of the locked vault
+    pub fn vault_cells(&self) -> Vec<Point> {
+        self.vault.clone()
+    }
+
+    /// Thin walls between two floor cells become windows of glass or iron bars
+    fn place_windows(&mut self) {
+        let mut rng = rand::thread_rng();
+        let is_wall = |p: Point| matches!(self.cell(p), Some(FieldCell::Wall));
+        let is_floor = |p: Point| matches!(self.cell(p), Some(FieldCell::Empty));
+
+        let mut thin_walls = Vec::new();
+
+        for y in 1..self.height - 1 {
+            for x in 1..self.width - 1 {
+                let p = Point::new(x, y);
+                let (w, e) = (p + Point::new(-1, 0), p + Point::new(1, 0));
+                let (n, s) = (p + Point::new(0, -1), p + Point::new(0, 1));
+
+                if is_wall(p)
+                    && ((is_wall(w) && is_wall(e) && is_floor(n) && is_floor(s))
+                        || (is_wall(n) && is_wall(s) && is_floor(w) && is_floor(e)))
+                {
+                    thin_walls.push(p);
+                }
+            }
+        }
+
+        let windows = std::iter::repeat_n(FieldCell::GlassWall, N_GLASS_WALLS)
+            .chain(std::iter::repeat_n(FieldCell::IronBars, N_IRON_BARS));
+
+        for window in windows {
+            if thin_walls.is_empty() {
+                return;
+            }
+
+            let p = thin_walls.remove(rng.gen_range(0, thin_walls.len()));
+            self.set_cell(p, window);
         }
     }
 
@@ -104,9 +463,13 @@ impl Field {
     fn smooth(&mut self) {
         let mut new_data = self.data.clone();
 
-        for y in 1..self.height - 1 {
+        for (y, new_row) in new_data
+            .iter_mut()
+            .enumerate()
+            .take(self.height - 1)
+            .skip(1)
+        {
             let row = &self.data[y];
-            let new_row = &mut new_data[y];
 
             for x in 1..self.width - 1 {
                 let n_walls = self.count_neighbour_walls(x, y);
@@ -118,9 +481,30 @@ impl Field {
         self.data = new_data;
     }
 
-    /// Create cave field
-    pub fn cave(width: usize, height: usize, prob_empty_cell: f32, smooth_repeats: usize) -> Field {
-        let mut field = Field::new(width, height);
+    /// Scale the field, every cell becomes a block of `x_scale` by `y_scale` cells
+    fn stretch(&mut self, x_scale: usize, y_scale: usize) {
+        let mut field = Field::new(self.width * x_scale, self.height * y_scale);
+        field.x_scale = self.x_scale * x_scale;
+
+        for y in 0..field.height {
+            for x in 0..field.width {
+                field.data[y][x] = self.data[y / y_scale][x / x_scale];
+            }
+        }
+
+        *self = field;
+    }
+
+    /// Create cave field. Caves generated `x_scale` times narrower and stretched back
+    /// look round on screens with tall cells
+    pub fn cave(
+        width: usize,
+        height: usize,
+        prob_empty_cell: f32,
+        smooth_repeats: usize,
+        x_scale: usize,
+    ) -> Field {
+        let mut field = Field::new(width / x_scale, height);
 
         field.fill_rand(prob_empty_cell);
         field.set_borders();
@@ -129,33 +513,239 @@ impl Field {
             field.smooth();
         }
 
+        field.stretch(x_scale, 1);
+        field.place_doors();
+        field.place_windows();
+        field.place_pools();
+        field.place_blobs(FieldCell::Lava, N_LAVA_POOLS, LAVA_POOL_SIZE);
+        field.place_blobs(FieldCell::Chasm, N_CHASMS, CHASM_SIZE);
+        field.place_blobs(FieldCell::TallFungus, N_FUNGUS_PATCHES, FUNGUS_PATCH_SIZE);
+        field.place_rubble();
+        field.place_vault();
+        field.place_traps();
+        field.place_lights();
+
         field
     }
 
-    pub fn is_wall(&self, x: i32, y: i32) -> bool {
-        if x < 0 || y < 0 {
-            return true;
+    /// Cell at the position, none outside of the field
+    pub fn cell(&self, p: Point) -> Option<FieldCell> {
+        if self.in_bounds(p) {
+            Some(self.data[p.y as usize][p.x as usize])
+        } else {
+            None
         }
+    }
 
-        let x = x as usize;
-        let y = y as usize;
+    pub fn set_cell(&mut self, p: Point, cell: FieldCell) {
+        if self.in_bounds(p) {
+            self.data[p.y as usize][p.x as usize] = cell;
+        }
+    }
+
+    /// Properties of the cell, outside of the field is solid rock
+    pub fn terrain(&self, p: Point) -> &'static Terrain {
+        self.cell(p).unwrap_or(FieldCell::Wall).terrain()
+    }
+
+    /// Cell stopping projectiles and particles
+    pub fn is_solid(&self, p: Point) -> bool {
+        self.terrain(p).solid
+    }
+
+    /// Rock that can be dug through, the border of the field and the vault walls are too hard
+    pub fn is_diggable(&self, p: Point) -> bool {
+        self.cell(p) == Some(FieldCell::Wall)
+            && !self.vault_walls.contains(&p)
+            && p.x > 0
+            && p.y > 0
+            && (p.x as usize) < self.width - 1
+            && (p.y as usize) < self.height - 1
+    }
 
-        if x >= self.width || y >= self.height {
-            return true;
+    pub fn is_walkable(&self, p: Point) -> bool {
+        self.terrain(p).walkable
+    }
+
+    /// Time to step on the cell relative to the bare floor
+    pub fn move_cost(&self, p: Point) -> f64 {
+        self.terrain(p).move_cost
+    }
+
+    /// Check if it is possible to step from `from` in `direction`.
+    /// Diagonal steps can not squeeze between two solid cells
+    pub fn can_step(&self, from: Point, direction: Direction) -> bool {
+        let delta = direction.delta();
+        let to = from + delta;
+
+        if !self.is_walkable(to) {
+            return false;
         }
 
-        match self.data[y as usize][x as usize] {
-            FieldCell::Wall => true,
-            FieldCell::Empty => false,
+        !(direction.is_diagonal()
+            && self.is_solid(Point::new(from.x + delta.x, from.y))
+            && self.is_solid(Point::new(from.x, from.y + delta.y)))
+    }
+
+    fn in_bounds(&self, p: Point) -> bool {
+        p.x >= 0 && p.y >= 0 && (p.x as usize) < self.width && (p.y as usize) < self.height
+    }
+
+    /// Remember all cells in fov
+    pub fn explore(&mut self, fov: &Fov) {
+        for p in fov {
+            if self.in_bounds(*p) {
+                self.explored[p.y as usize][p.x as usize] = true;
+            }
         }
     }
 
+    pub fn is_explored(&self, p: Point) -> bool {
+        self.in_bounds(p) && self.explored[p.y as usize][p.x as usize]
+    }
+
+    /// Explored cell from which an unexplored one can be seen
+    fn is_frontier(&self, p: Point) -> bool {
+        self.is_explored(p)
+            && self.is_walkable(p)
+            && Direction::ALL.iter().any(|d| {
+                let n = p + d.delta();
+                self.in_bounds(n) && !self.is_explored(n)
+            })
+    }
+
+    /// Next step on the shortest explored path to the nearest unexplored place
+    pub fn explore_step(&self, from: Point) -> Option<Direction> {
+        let starts: Vec<_> = self
+            .empty_cells()
+            .into_iter()
+            .filter(|p| self.is_frontier(*p))
+            .map(|p| self.point2d_to_index(p))
+            .collect();
+
+        if starts.is_empty() {
+            return None;
+        }
+
+        let map = DijkstraMap::new(self.width, self.height, &starts, self, EXPLORE_MAX_DEPTH);
+        let from_idx = self.point2d_to_index(from);
+
+        if map.map[from_idx] == f32::MAX {
+            return None;
+        }
+
+        let next = DijkstraMap::find_lowest_exit(&map, from_idx, self)?;
+
+        if map.map[next] >= map.map[from_idx] {
+            return None;
+        }
+
+        Direction::from_delta(self.index_to_point2d(next) - from)
+    }
+
+    /// Path through explored cells, without the starting point
+    pub fn travel_path(&self, from: Point, to: Point) -> Option<Vec<Point>> {
+        if !self.is_explored(to) || !self.is_walkable(to) || from == to {
+            return None;
+        }
+
+        let path = a_star_search(self.point2d_to_index(from), self.point2d_to_index(to), self);
+
+        if !path.success {
+            return None;
+        }
+
+        Some(
+            path.steps
+                .into_iter()
+                .skip(1)
+                .map(|idx| self.index_to_point2d(idx))
+                .collect(),
+        )
+    }
+
+    /// Leave a mark on the cell, it replaces an older one. Water and chasms keep no marks
+    pub fn add_decal(&mut self, p: Point, kind: DecalKind) {
+        if self.in_bounds(p) && self.terrain(p).stains {
+            self.decals[p.y as usize][p.x as usize] = Some(Decal::new(kind));
+        }
+    }
+
+    /// Let decals dry for the number of turns
+    pub fn age_decals(&mut self, turns: f64) {
+        for decal in self.decals.iter_mut().flatten().flatten() {
+            decal.grow_old(turns);
+        }
+    }
+
+    /// Glyph, color and background of the cell, decals cover the floor and tint solid cells
+    fn appearance(&self, x: usize, y: usize) -> (&'static str, RGBA, RGBA) {
+        let terrain = self.data[y][x].terrain();
+
+        match self.decals[y][x] {
+            Some(decal) if terrain.solid => (terrain.glyph, decal.color(), terrain.bg()),
+            Some(decal) => (decal.glyph(), decal.color(), terrain.bg()),
+            None => (terrain.glyph, terrain.color(), terrain.bg()),
+        }
+    }
+
+    /// Draw cell which is explored but not visible now
+    fn draw_remembered(&self, ctx: &mut BTerm, x: usize, y: usize, pos: Point) {
+        let (glyph, _, _) = self.appearance(x, y);
+        let color = match self.decals[y][x] {
+            Some(decal) => decal.remembered_color(),
+            None => palette().remembered,
+        };
+
+        ctx.print_color(pos.x, pos.y, color, palette().bg, glyph)
+    }
+
+    /// Draw visible cell in the light of its place
+    fn draw_lit(&self, ctx: &mut BTerm, x: usize, y: usize, pos: Point) {
+        let (glyph, color, bg) = self.appearance(x, y);
+        let color = self.light.tint(color, Point::new(x, y));
+        let bg = self.light.tint(bg, Point::new(x, y));
+
+        ctx.print_color(pos.x, pos.y, color, bg, glyph)
+    }
+
+    /// Name of an explored cell
+    pub fn describe(&self, p: Point) -> Option<String> {
+        if !self.is_explored(p) {
+            return None;
+        }
+
+        let name = self.terrain(p).name;
+
+        let mut description = match self.light_at(p) {
+            Some(light) => format!("{} on the {}", light.kind.name(), name),
+            None => name.to_string(),
+        };
+
+        if let Some(decal) = self.decals[p.y as usize][p.x as usize] {
+            description = format!("{}, {}", description, decal.name());
+        }
+
+        if self.is_known_trap(p) {
+            if let Some(trap) = self.trap_at(p) {
+                description = format!("{}, {}", description, trap.kind.name());
+            }
+        }
+
+        Some(description)
+    }
+
     pub fn empty_cells(&self) -> Vec<Point> {
+        self.cells_of(FieldCell::Empty)
+    }
+
+    /// Positions of all cells of the kind
+    pub fn cells_of(&self, cell: FieldCell) -> Vec<Point> {
         let mut list = Vec::new();
 
         for y in 0..self.height {
             for x in 0..self.width {
-                if let FieldCell::Empty = self.data[y][x] {
+                if self.data[y][x] == cell {
                     list.push(Point::new(x as i32, y as i32))
                 }
             }
@@ -177,7 +767,42 @@ impl Draw for Field {
 }
 
 impl DrawWithFov for Field {
+    /// Prints explored cells as remembered and cells in fov as they are
     fn draw_with_fov(&self, ctx: &mut BTerm, fov: &Fov, pos: Point, fov_pos: Point) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.explored[y][x] {
+                    self.draw_remembered(ctx, x, y, Point::new(x, y) + pos);
+                }
+            }
+        }
+
+        for light in &self.lights {
+            if self.is_explored(light.pos) {
+                let p = light.pos + pos;
+                ctx.print_color(
+                    p.x,
+                    p.y,
+                    palette().remembered,
+                    palette().bg,
+                    light.kind.glyph(),
+                );
+            }
+        }
+
+        for trap in &self.traps {
+            if !trap.is_hidden() && self.is_explored(trap.pos) {
+                let p = trap.pos + pos;
+                ctx.print_color(
+                    p.x,
+                    p.y,
+                    palette().remembered,
+                    palette().bg,
+                    trap.kind.glyph(),
+                );
+            }
+        }
+
         for p in fov {
             let p = *p - fov_pos;
 
@@ -192,7 +817,26 @@ impl DrawWithFov for Field {
                 continue;
             }
 
-            self.data[y][x].draw(ctx, p + pos);
+            let trap = self.trap_at(p).filter(|t| !t.is_hidden());
+
+            match (self.light_at(p), trap) {
+                (Some(light), _) => {
+                    let p = p + pos;
+                    ctx.print_color(
+                        p.x,
+                        p.y,
+                        light.kind.color(),
+                        palette().bg,
+                        light.kind.glyph(),
+                    );
+                }
+                (None, Some(trap)) => {
+                    let color = self.light.tint(trap.kind.color(), p);
+                    let p = p + pos;
+                    ctx.print_color(p.x, p.y, color, palette().bg, trap.kind.glyph());
+                }
+                (None, None) => self.draw_lit(ctx, x, y, p + pos),
+            }
         }
     }
 }
@@ -205,9 +849,29 @@ impl Algorithm2D for Field {
 
 impl BaseMap for Field {
     fn is_opaque(&self, idx: usize) -> bool {
-        let x = idx % self.width;
-        let y = idx / self.width;
-        self.is_wall(x as i32, y as i32)
+        self.terrain(self.index_to_point2d(idx)).opaque
+    }
+
+    /// Steps between explored cells, the player plans paths only through known places
+    /// and around known traps
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        let from = self.index_to_point2d(idx);
+
+        Direction::ALL
+            .iter()
+            .filter(|d| {
+                let to = from + d.delta();
+                self.can_step(from, **d) && self.is_explored(to) && !self.is_known_trap(to)
+            })
+            .map(|d| {
+                let to = from + d.delta();
+                (self.point2d_to_index(to), self.move_cost(to) as f32)
+            })
+            .collect()
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        DistanceAlg::Chebyshev.distance2d(self.index_to_point2d(idx1), self.index_to_point2d(idx2))
     }
 }
 
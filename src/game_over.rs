@@ -0,0 +1,103 @@
+use crate::colors::palette;
+use crate::draw::BTerm;
+
+/// Skull flashed right after the death
+const SKULL_FLASH: &str = r###"
+               :|hwh     .m::whX
+             X*#ma$     X mUUUUUUww:
+                  .h    U UUUUUUUUUU:X
+                        U #UUUUUUUUUU:X
+               h -     |UX .RUUUUUUUUmm
+                     XwUUU|  .UUUUUURmm
+                    m T#UUUUwX..#mRRmmm
+            .wu:iw*      #UUUU:    ..
+          X  mUUUU         T#UT  :Uw|X|
+         %    #UUUm             .UUUUUU
+               TUUUU8::    :ww     ##*
+        -        .T#UUaawa*.UU      /
+Uaam                  X|wUw
+      :%      h     wmUUUUTi     w|n+
+     X     .h  u  UUUBUUU w | TUUm
+   Xa       .awTwo( *UUUwUThU
+i   XU.         .UUUBUwu( **URm
+Rai               UUUUUBUUen
+"###;
+
+/// Skull under the summary
+const SKULL: &str = r###"
+                      :::!~!!!!!:.
+                  .xUHWH!! !!?M88WHX:.
+                .X*#M@$!!  !X!M$$$$$$WWx:.
+               :!!!!!!?H! :!$!$$$$$$$$$$8X:
+              !!~  ~:~!! :~!$!#$$$$$$$$$$8X:
+             :!~::!H!<   ~.U$X!?R$$$$$$$$MM!
+             ~!~!!!!~~ .:XW$$$U!!?$$$$$$RMM!
+               !:~~~ .:!M"T#$$$$WX??#MRRMMM!
+               ~?WuxiW*`   `"#$$$$8!!!!??!!!
+             :X- M$$$$       `"T#$T~!8$WUXU~
+            :%`  ~#$$$m:        ~!~ ?$$$$$$
+          :!`.-   ~T$$$$8xx.  .xWW- ~""##*"
+.....   -~~:<` !    ~?T#$$@@W@*?$$      /`
+W$@@M!!! .!~~ !!     .:XUW$W!~ `"~:    :
+#"~~`.:x%`!!  !H:   !WM$$$$Ti.: .!WUn+!`
+:::~:!!`:X~ .: ?H.!u "$$$B$$$!W:U!T$$M~
+.~~   :X@!.-~   ?@WTWo("*$$$W$TH$! `
+Wi.~!X$?!-~    : ?$$$B$Wu("**$RM!
+$R@i.~~ !     :   ~$$$$$B$$en:``
+?MXT@Wx.~    :     ~"##*$$$$M~
+"###;
+
+/// Time the first skull is shown
+const FLASH_MS: f64 = 300.0;
+
+/// Results of the finished game
+pub struct GameSummary {
+    pub kills: u32,
+    pub archetype: &'static str,
+    pub level: u32,
+    pub experience: u32,
+    /// Names of the perks taken on level ups
+    pub perks: Vec<&'static str>,
+}
+
+impl GameSummary {
+    /// Flash a skull, then show results with the hint on keys.
+    /// Skulls are drawn at the bottom of the screen
+    pub fn draw(&self, ctx: &mut BTerm, elapsed_ms: f64, screen_height: u64, hint: &[String]) {
+        let color = palette().log;
+        let bg = palette().bg;
+
+        let skull = if elapsed_ms < FLASH_MS {
+            SKULL_FLASH
+        } else {
+            SKULL
+        };
+
+        let art: Vec<_> = skull.trim_matches('\n').lines().collect();
+        let top = screen_height.saturating_sub(art.len() as u64);
+
+        for (i, line) in art.iter().enumerate() {
+            ctx.print_color(0, top + i as u64, color, bg, line);
+        }
+
+        if elapsed_ms < FLASH_MS {
+            return;
+        }
+
+        let mut lines = vec![
+            format!("Kills: {}", self.kills),
+            format!(
+                "Character: {}, level {} ({} experience)",
+                self.archetype, self.level, self.experience
+            ),
+        ];
+
+        if !self.perks.is_empty() {
+            lines.push(format!("Perks: {}", self.perks.join(", ")));
+        }
+
+        for (i, line) in lines.iter().chain(hint).enumerate() {
+            ctx.print_color(0, i as u64, color, bg, line);
+        }
+    }
+}
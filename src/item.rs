@@ -0,0 +1,158 @@
+use crate::colors::{palette, RGBA};
+use crate::draw::{BTerm, DrawLit, Fov, Point};
+use crate::field::Field;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub enum ItemKind {
+    Bow,
+    Arrow,
+    ThrowingKnife,
+    OilFlask,
+    /// Opens a locked door, the key stays in the lock
+    Key,
+    Pickaxe,
+}
+
+impl ItemKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bow => "bow",
+            Self::Arrow => "arrow",
+            Self::ThrowingKnife => "throwing knife",
+            Self::OilFlask => "oil flask",
+            Self::Key => "key",
+            Self::Pickaxe => "pickaxe",
+        }
+    }
+
+    pub fn plural(self) -> String {
+        match self {
+            Self::ThrowingKnife => "throwing knives".to_string(),
+            _ => format!("{}s", self.name()),
+        }
+    }
+
+    /// Name with an article, like "an arrow"
+    fn name_article(self) -> String {
+        let name = self.name();
+
+        match name.chars().next() {
+            Some('a' | 'e' | 'i' | 'o' | 'u') => format!("an {}", name),
+            _ => format!("a {}", name),
+        }
+    }
+
+    /// Name with a count, like "3 arrows"
+    pub fn name_count(self, count: u32) -> String {
+        match count {
+            1 => self.name_article(),
+            _ => format!("{} {}", count, self.plural()),
+        }
+    }
+
+    /// Weight of a single item, a heavy load slows the player down
+    pub fn weight(self) -> f64 {
+        match self {
+            Self::Bow => 2.0,
+            Self::Arrow => 0.1,
+            Self::ThrowingKnife => 0.5,
+            Self::OilFlask => 1.0,
+            Self::Key => 0.1,
+            Self::Pickaxe => 5.0,
+        }
+    }
+
+    fn glyph(self) -> char {
+        match self {
+            Self::Bow => '}',
+            Self::Arrow => '/',
+            Self::ThrowingKnife => ')',
+            Self::OilFlask => '!',
+            Self::Key => '¬',
+            Self::Pickaxe => '(',
+        }
+    }
+
+    fn color(self) -> RGBA {
+        palette().item
+    }
+}
+
+/// Stack of items lying on the field
+pub struct FloorItem {
+    pub kind: ItemKind,
+    pub count: u32,
+    pub pos: Point,
+}
+
+impl FloorItem {
+    pub fn new(kind: ItemKind, count: u32, pos: Point) -> Self {
+        Self { kind, count, pos }
+    }
+
+    pub fn description(&self) -> String {
+        self.kind.name_count(self.count)
+    }
+}
+
+impl DrawLit for FloorItem {
+    fn draw_lit(&self, ctx: &mut BTerm, fov: &Fov, field: &Field, pos: Point) {
+        if fov.contains(&self.pos) {
+            let color = field.tint(self.kind.color(), self.pos);
+            ctx.print_color(pos.x, pos.y, color, palette().bg, self.kind.glyph())
+        }
+    }
+}
+
+/// Put items to the field, stacks of the same kind are merged
+pub fn drop_item(items: &mut Vec<FloorItem>, kind: ItemKind, count: u32, pos: Point) {
+    match items.iter_mut().find(|i| i.kind == kind && i.pos == pos) {
+        Some(item) => item.count += count,
+        None => items.push(FloorItem::new(kind, count, pos)),
+    }
+}
+
+#[derive(Default)]
+pub struct Inventory {
+    items: BTreeMap<ItemKind, u32>,
+}
+
+impl Inventory {
+    pub fn add(&mut self, kind: ItemKind, count: u32) {
+        *self.items.entry(kind).or_insert(0) += count;
+    }
+
+    pub fn count(&self, kind: ItemKind) -> u32 {
+        self.items.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// Take one item of the kind if there is any
+    pub fn take(&mut self, kind: ItemKind) -> bool {
+        match self.items.get_mut(&kind) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                if *count == 0 {
+                    self.items.remove(&kind);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.items
+            .iter()
+            .map(|(kind, count)| kind.weight() * *count as f64)
+            .sum()
+    }
+
+    pub fn description(&self) -> String {
+        self.items
+            .iter()
+            .map(|(kind, count)| kind.name_count(*count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
@@ -0,0 +1,251 @@
+use crate::config::{read_entries, ConfigError, ConfigResult};
+use crate::direction::Direction;
+use bracket_terminal::prelude::VirtualKeyCode;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Game commands that can be bound to keys
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Command {
+    Move(Direction),
+    Wait,
+    Rest,
+    Explore,
+    PickUp,
+    Refuel,
+    Close,
+    Search,
+    Dig,
+    Ability,
+    Fire,
+    Throw,
+    NextTarget,
+    Confirm,
+    Look,
+    Cancel,
+    Help,
+    NextTheme,
+    Restart,
+    Quit,
+}
+
+impl Command {
+    pub fn all() -> Vec<Command> {
+        let mut commands: Vec<_> = Direction::ALL.iter().map(|d| Self::Move(*d)).collect();
+        commands.extend_from_slice(&[
+            Self::Wait,
+            Self::Rest,
+            Self::Explore,
+            Self::PickUp,
+            Self::Refuel,
+            Self::Close,
+            Self::Search,
+            Self::Dig,
+            Self::Ability,
+            Self::Fire,
+            Self::Throw,
+            Self::NextTarget,
+            Self::Confirm,
+            Self::Look,
+            Self::Cancel,
+            Self::Help,
+            Self::NextTheme,
+            Self::Restart,
+            Self::Quit,
+        ]);
+        commands
+    }
+
+    /// Name used in the keymap file
+    pub fn name(self) -> String {
+        match self {
+            Self::Move(direction) => format!("move_{}", direction.name()),
+            Self::Wait => "wait".to_string(),
+            Self::Rest => "rest".to_string(),
+            Self::Explore => "explore".to_string(),
+            Self::PickUp => "pick_up".to_string(),
+            Self::Refuel => "refuel".to_string(),
+            Self::Close => "close_door".to_string(),
+            Self::Search => "search".to_string(),
+            Self::Dig => "dig".to_string(),
+            Self::Ability => "ability".to_string(),
+            Self::Fire => "fire".to_string(),
+            Self::Throw => "throw".to_string(),
+            Self::NextTarget => "next_target".to_string(),
+            Self::Confirm => "confirm".to_string(),
+            Self::Look => "look".to_string(),
+            Self::Cancel => "cancel".to_string(),
+            Self::Help => "help".to_string(),
+            Self::NextTheme => "next_theme".to_string(),
+            Self::Restart => "restart".to_string(),
+            Self::Quit => "quit".to_string(),
+        }
+    }
+
+    pub fn description(self) -> String {
+        match self {
+            Self::Move(direction) => format!("move {}", direction.name().replace('_', "-")),
+            Self::Wait => "wait a turn".to_string(),
+            Self::Rest => "rest until interrupted".to_string(),
+            Self::Explore => "auto-explore".to_string(),
+            Self::PickUp => "pick up items".to_string(),
+            Self::Refuel => "refuel the lantern".to_string(),
+            Self::Close => "close a door".to_string(),
+            Self::Search => "search for traps".to_string(),
+            Self::Dig => "dig with a pickaxe".to_string(),
+            Self::Ability => "use your ability".to_string(),
+            Self::Fire => "fire the bow".to_string(),
+            Self::Throw => "throw a knife".to_string(),
+            Self::NextTarget => "next target".to_string(),
+            Self::Confirm => "confirm".to_string(),
+            Self::Look => "look around".to_string(),
+            Self::Cancel => "cancel".to_string(),
+            Self::Help => "show this help".to_string(),
+            Self::NextTheme => "switch color theme".to_string(),
+            Self::Restart => "restart after death".to_string(),
+            Self::Quit => "quit the game".to_string(),
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Command> {
+        Self::all().into_iter().find(|c| c.name() == name)
+    }
+}
+
+/// Keys that can be used in the keymap file
+#[rustfmt::skip]
+const KEYS: &[VirtualKeyCode] = {
+    use VirtualKeyCode::*;
+    &[
+        Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0, A, B, C, D, E, F, G, H, I, J,
+        K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9,
+        F10, F11, F12, Insert, Home, Delete, End, PageDown, PageUp, Left, Up, Right, Down, Back,
+        Return, Space, Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7,
+        Numpad8, Numpad9, NumpadEnter, Apostrophe, Backslash, Comma, Equals, Grave, LBracket,
+        RBracket, Minus, Period, Semicolon, Slash, Tab,
+    ]
+};
+
+fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+    KEYS.iter().copied().find(|k| key_name(*k) == name)
+}
+
+pub fn key_name(key: VirtualKeyCode) -> String {
+    format!("{:?}", key)
+}
+
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: HashMap<VirtualKeyCode, Command>,
+}
+
+impl Default for Keymap {
+    /// Arrows, numpad and vi-keys for movement
+    fn default() -> Self {
+        use Direction::*;
+        use VirtualKeyCode as Key;
+
+        let mut keymap = Self {
+            bindings: HashMap::new(),
+        };
+
+        let moves = [
+            (North, [Key::Up, Key::Numpad8, Key::K]),
+            (NorthEast, [Key::PageUp, Key::Numpad9, Key::U]),
+            (East, [Key::Right, Key::Numpad6, Key::L]),
+            (SouthEast, [Key::PageDown, Key::Numpad3, Key::N]),
+            (South, [Key::Down, Key::Numpad2, Key::J]),
+            (SouthWest, [Key::End, Key::Numpad1, Key::B]),
+            (West, [Key::Left, Key::Numpad4, Key::H]),
+            (NorthWest, [Key::Home, Key::Numpad7, Key::Y]),
+        ];
+
+        for (direction, keys) in moves.iter() {
+            for key in keys {
+                keymap.bind(*key, Command::Move(*direction));
+            }
+        }
+
+        keymap.bind(Key::Period, Command::Wait);
+        keymap.bind(Key::Numpad5, Command::Wait);
+        keymap.bind(Key::R, Command::Rest);
+        keymap.bind(Key::O, Command::Explore);
+        keymap.bind(Key::G, Command::PickUp);
+        keymap.bind(Key::Comma, Command::PickUp);
+        keymap.bind(Key::E, Command::Refuel);
+        keymap.bind(Key::C, Command::Close);
+        keymap.bind(Key::S, Command::Search);
+        keymap.bind(Key::D, Command::Dig);
+        keymap.bind(Key::A, Command::Ability);
+        keymap.bind(Key::F, Command::Fire);
+        keymap.bind(Key::T, Command::Throw);
+        keymap.bind(Key::Tab, Command::NextTarget);
+        keymap.bind(Key::Return, Command::Confirm);
+        keymap.bind(Key::X, Command::Look);
+        keymap.bind(Key::Escape, Command::Cancel);
+        keymap.bind(Key::Slash, Command::Help);
+        keymap.bind(Key::F1, Command::Help);
+        keymap.bind(Key::F2, Command::NextTheme);
+        keymap.bind(Key::Space, Command::Restart);
+        keymap.bind(Key::Q, Command::Quit);
+
+        keymap
+    }
+}
+
+impl Keymap {
+    /// Load keymap from a file of `Key = command` lines on top of the default one.
+    /// `Key = none` removes the default binding of a key
+    pub fn load(path: &Path) -> ConfigResult<Keymap> {
+        let mut keymap = Keymap::default();
+
+        let entries = match read_entries(path)? {
+            Some(entries) => entries,
+            None => return Ok(keymap),
+        };
+
+        for entry in entries {
+            let key = key_from_name(&entry.key).ok_or_else(|| {
+                ConfigError::new(path, entry.line, format!("unknown key `{}`", entry.key))
+            })?;
+
+            if entry.value == "none" {
+                keymap.bindings.remove(&key);
+                continue;
+            }
+
+            let command = Command::from_name(&entry.value).ok_or_else(|| {
+                ConfigError::new(
+                    path,
+                    entry.line,
+                    format!("unknown command `{}`", entry.value),
+                )
+            })?;
+
+            keymap.bind(key, command);
+        }
+
+        Ok(keymap)
+    }
+
+    fn bind(&mut self, key: VirtualKeyCode, command: Command) {
+        self.bindings.insert(key, command);
+    }
+
+    pub fn command(&self, key: VirtualKeyCode) -> Option<Command> {
+        self.bindings.get(&key).copied()
+    }
+
+    /// All keys bound to the command in a stable order
+    pub fn keys(&self, command: Command) -> Vec<VirtualKeyCode> {
+        let mut keys: Vec<_> = self
+            .bindings
+            .iter()
+            .filter(|(_, c)| **c == command)
+            .map(|(k, _)| *k)
+            .collect();
+
+        keys.sort();
+        keys
+    }
+}
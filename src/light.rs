@@ -0,0 +1,153 @@
+use crate::colors::{palette, RGBA};
+use crate::draw::Point;
+use bracket_pathfinding::prelude::{field_of_view_set, Algorithm2D};
+
+/// Share of the own color seen in complete darkness
+const AMBIENT_LIGHT: f32 = 0.3;
+/// Minimal light level required to see a cell
+pub const MIN_VISIBLE_LIGHT: f32 = 0.15;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LightKind {
+    Torch,
+    GlowingMoss,
+    /// Light carried by the player
+    Carried,
+}
+
+impl LightKind {
+    pub fn color(self) -> RGBA {
+        match self {
+            Self::Torch => palette().torch,
+            Self::GlowingMoss => palette().glowing_moss,
+            Self::Carried => palette().player_light,
+        }
+    }
+
+    pub fn radius(self) -> i32 {
+        match self {
+            Self::Torch => 6,
+            Self::GlowingMoss => 2,
+            Self::Carried => 6,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Torch => "torch",
+            Self::GlowingMoss => "glowing moss",
+            Self::Carried => "light",
+        }
+    }
+
+    pub fn glyph(self) -> char {
+        match self {
+            Self::Torch => '☼',
+            Self::GlowingMoss => '"',
+            Self::Carried => '*',
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct LightSource {
+    pub kind: LightKind,
+    pub pos: Point,
+    pub radius: i32,
+}
+
+impl LightSource {
+    pub fn new(kind: LightKind, pos: Point) -> Self {
+        Self {
+            kind,
+            pos,
+            radius: kind.radius(),
+        }
+    }
+
+    pub fn with_radius(mut self, radius: i32) -> Self {
+        self.radius = radius;
+        self
+    }
+}
+
+/// Light color in every cell of a field
+pub struct LightMap {
+    width: usize,
+    height: usize,
+    light: Vec<RGBA>,
+}
+
+impl LightMap {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            light: vec![RGBA::from_f32(0.0, 0.0, 0.0, 1.0); width * height],
+        }
+    }
+
+    fn index(&self, p: Point) -> Option<usize> {
+        if p.x < 0 || p.y < 0 || p.x as usize >= self.width || p.y as usize >= self.height {
+            None
+        } else {
+            Some(p.y as usize * self.width + p.x as usize)
+        }
+    }
+
+    /// Add light of the source to every cell in its sight. It fades linearly with distance.
+    /// Horizontal distances are divided by `x_scale` to keep light round on a stretched map
+    pub fn add(&mut self, source: &LightSource, map: &dyn Algorithm2D, x_scale: i32) {
+        if source.radius <= 0 {
+            return;
+        }
+
+        let color = source.kind.color();
+
+        for p in field_of_view_set(source.pos, source.radius * x_scale, map) {
+            let delta = p - source.pos;
+            let distance =
+                ((delta.x as f32 / x_scale as f32).powi(2) + (delta.y as f32).powi(2)).sqrt();
+            let power = 1.0 - distance / (source.radius as f32 + 1.0);
+
+            if power <= 0.0 {
+                continue;
+            }
+
+            if let Some(i) = self.index(p) {
+                let light = &mut self.light[i];
+                light.r = f32::min(light.r + color.r * power, 1.0);
+                light.g = f32::min(light.g + color.g * power, 1.0);
+                light.b = f32::min(light.b + color.b * power, 1.0);
+            }
+        }
+    }
+
+    /// Brightness of the cell from 0 to 1
+    pub fn level(&self, p: Point) -> f32 {
+        match self.index(p) {
+            Some(i) => {
+                let light = self.light[i];
+                f32::max(light.r, f32::max(light.g, light.b))
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Color of an object lit by the cell light
+    pub fn tint(&self, color: RGBA, p: Point) -> RGBA {
+        let light = match self.index(p) {
+            Some(i) => self.light[i],
+            None => return color,
+        };
+
+        let channel = |c: f32, l: f32| c * (AMBIENT_LIGHT + (1.0 - AMBIENT_LIGHT) * l);
+
+        RGBA::from_f32(
+            channel(color.r, light.r),
+            channel(color.g, light.g),
+            channel(color.b, light.b),
+            color.a,
+        )
+    }
+}
@@ -0,0 +1,40 @@
+use crate::colors::palette;
+use crate::draw::{BTerm, Draw, Point};
+
+const MAX_MESSAGES: usize = 100;
+/// Number of recent messages shown
+const SHOWN_MESSAGES: usize = 2;
+
+/// Messages about what happens in the game
+#[derive(Default)]
+pub struct Log {
+    messages: Vec<String>,
+}
+
+impl Log {
+    pub fn add<S: ToString>(&mut self, message: S) {
+        self.messages.push(message.to_string());
+
+        if self.messages.len() > MAX_MESSAGES {
+            self.messages.remove(0);
+        }
+    }
+}
+
+impl Draw for Log {
+    /// Prints recent messages, the newest one is at the bottom
+    fn draw(&self, ctx: &mut BTerm, pos: Point) {
+        let skip = self.messages.len().saturating_sub(SHOWN_MESSAGES);
+
+        for (i, message) in self.messages.iter().skip(skip).enumerate() {
+            let newest = skip + i + 1 == self.messages.len();
+            let color = if newest {
+                palette().log
+            } else {
+                palette().log_old
+            };
+
+            ctx.print_color(pos.x, pos.y + i as i32, color, palette().bg, message);
+        }
+    }
+}
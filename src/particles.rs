@@ -1,106 +1,286 @@
-use crate::colors::{COLOR_BG, COLOR_BLOOD};
-use crate::draw::{BTerm, Draw, Point};
+use crate::colors::{palette, RGBA};
+use crate::decal::DecalKind;
+use crate::draw::{BTerm, Draw, DrawWithFov, Fov, Point};
+use crate::field::Field;
+use crate::state::State;
 use rand::Rng;
-use std::time::{Duration, Instant};
+use std::collections::HashMap;
+
+/// Speed of a particle launched with power 1, cells per millisecond
+const BASE_SPEED: f64 = 1.0 / 125.0;
+
+/// What happens to a particle flying into a wall
+enum Collision {
+    /// The particle stays in front of the wall
+    Stop,
+    /// The particle is gone and may leave a mark on the wall
+    Splatter,
+}
+
+#[derive(Clone, Copy)]
+pub enum ParticleKind {
+    Blood,
+    /// Sparks of a weapon hitting stone
+    Spark,
+    /// Dust raised by steps
+    Dust,
+    Smoke,
+    Magic,
+}
+
+impl ParticleKind {
+    /// Glyphs from a fresh particle to a fading one
+    fn glyphs(self) -> &'static [char] {
+        match self {
+            Self::Blood => &['█', '▒'],
+            Self::Spark => &['*', '+', '.'],
+            Self::Dust => &['░', '.'],
+            Self::Smoke => &['▓', '▒', '░'],
+            Self::Magic => &['*', '+', '·'],
+        }
+    }
+
+    /// Colors of a fresh and of a faded particle
+    fn colors(self) -> (RGBA, RGBA) {
+        match self {
+            Self::Blood => (palette().blood, palette().blood),
+            Self::Spark => (palette().spark, palette().spark_faded),
+            Self::Dust => (palette().dust, palette().dust_faded),
+            Self::Smoke => (palette().smoke, palette().smoke_faded),
+            Self::Magic => (palette().magic, palette().magic_faded),
+        }
+    }
+
+    /// Shortest lifetime and its random addition in milliseconds
+    fn lifetime_ms(self) -> (u64, u64) {
+        match self {
+            Self::Blood => (375, 375),
+            Self::Spark => (150, 200),
+            Self::Dust => (200, 200),
+            Self::Smoke => (600, 600),
+            Self::Magic => (300, 300),
+        }
+    }
+
+    /// Acceleration down the screen in cells per square millisecond,
+    /// smoke rises so its gravity is negative
+    fn gravity(self) -> f64 {
+        match self {
+            Self::Blood => 0.000_005,
+            Self::Spark => 0.000_02,
+            Self::Dust => 0.0,
+            Self::Smoke => -0.000_005,
+            Self::Magic => 0.0,
+        }
+    }
+
+    fn collision(self) -> Collision {
+        match self {
+            Self::Blood | Self::Spark => Collision::Splatter,
+            Self::Dust | Self::Smoke | Self::Magic => Collision::Stop,
+        }
+    }
+
+    /// Mark left where the particle lands or splatters
+    fn decal(self) -> Option<DecalKind> {
+        match self {
+            Self::Blood => Some(DecalKind::Blood),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Clone)]
-struct BloodParticle {
+struct Particle {
+    kind: ParticleKind,
     pos: Point,
     speed: (f64, f64),
     error: (f64, f64),
-    destroy_after: Instant,
+    age_ms: f64,
+    lifetime_ms: f64,
+    splattered: bool,
 }
 
-pub struct BloodParticlesEffect {
-    particles: Vec<BloodParticle>,
-    prev_process: Instant,
-}
+impl Particle {
+    fn is_expired(&self) -> bool {
+        self.age_ms >= self.lifetime_ms
+    }
+
+    /// Part of the lifetime passed, from 0 to 1
+    fn age(&self) -> f32 {
+        (self.age_ms / self.lifetime_ms.max(1.0)).min(1.0) as f32
+    }
+
+    /// Move the particle, returns the wall it has splattered against
+    fn step(&mut self, field: &Field, delta: f64) -> Option<Point> {
+        self.age_ms += delta;
+        self.speed.1 += self.kind.gravity() * delta;
+
+        self.error.0 += self.speed.0 * delta;
+        self.error.1 += self.speed.1 * delta;
 
-impl BloodParticle {
-    fn time_left(&self) -> u128 {
-        self.destroy_after
-            .saturating_duration_since(Instant::now())
-            .as_millis()
+        let mut next = self.pos;
+
+        if self.error.0.abs() >= 0.5 {
+            next.x += self.error.0.signum() as i32;
+            self.error.0 -= self.error.0.signum();
+        }
+
+        if self.error.1.abs() >= 0.5 {
+            next.y += self.error.1.signum() as i32;
+            self.error.1 -= self.error.1.signum();
+        }
+
+        if !field.is_solid(next) {
+            self.pos = next;
+            return None;
+        }
+
+        match self.kind.collision() {
+            Collision::Stop => {
+                self.speed = (0.0, 0.0);
+                self.error = (0.0, 0.0);
+                None
+            }
+            Collision::Splatter => {
+                self.splattered = true;
+                Some(next)
+            }
+        }
     }
 }
 
-impl BloodParticlesEffect {
+/// All particles flying over the field
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+}
+
+impl ParticleSystem {
     pub fn new() -> Self {
         Self {
             particles: Vec::new(),
-            prev_process: Instant::now(),
         }
     }
 
-    pub fn spawn(&mut self, pos: Point, speed: (f64, f64), number: usize, power: f64) {
+    /// Emit particles in a half circle around the direction of the speed
+    pub fn spawn(
+        &mut self,
+        kind: ParticleKind,
+        pos: Point,
+        speed: (f64, f64),
+        number: usize,
+        power: f64,
+    ) {
+        self.emit(kind, pos, speed, number, power, std::f64::consts::PI);
+    }
+
+    /// Emit particles to all sides
+    pub fn burst(&mut self, kind: ParticleKind, pos: Point, number: usize, power: f64) {
+        self.emit(
+            kind,
+            pos,
+            (1.0, 0.0),
+            number,
+            power,
+            2.0 * std::f64::consts::PI,
+        );
+    }
+
+    fn emit(
+        &mut self,
+        kind: ParticleKind,
+        pos: Point,
+        speed: (f64, f64),
+        number: usize,
+        power: f64,
+        spread: f64,
+    ) {
         let mut rng = rand::thread_rng();
+        let (min_lifetime, lifetime_spread) = kind.lifetime_ms();
 
         for _ in 0..number {
             let (sp_x, sp_y) = speed;
-            let k = power * (rng.gen::<f64>() * 0.2 + 0.9) / 125.0;
+            let k = power * (rng.gen::<f64>() * 0.2 + 0.9) * BASE_SPEED;
             let (sp_x, sp_y) = (sp_x * k, sp_y * k);
 
-            let phi = (rng.gen::<f64>() - 0.5) * 3.14;
+            let phi = (rng.gen::<f64>() - 0.5) * spread;
 
             let speed = (
                 sp_x * phi.cos() + sp_y * phi.sin(),
                 sp_y * phi.cos() - sp_x * phi.sin(),
             );
 
-            let duration = Duration::from_millis(rng.gen::<u64>() % 375 + 375);
+            let lifetime = rng.gen::<u64>() % lifetime_spread.max(1) + min_lifetime;
 
-            let particle = BloodParticle {
+            self.particles.push(Particle {
+                kind,
                 pos,
                 speed,
                 error: (0.0, 0.0),
-                destroy_after: Instant::now() + duration,
-            };
-
-            self.particles.push(particle)
+                age_ms: 0.0,
+                lifetime_ms: lifetime as f64,
+                splattered: false,
+            })
         }
     }
 
-    pub fn process(&mut self) {
-        self.particles = self
+    /// Move particles, returns decals left where particles have landed or splattered
+    pub fn process(&mut self, world: &State) -> Vec<(Point, DecalKind)> {
+        let mut decals: Vec<_> = self
             .particles
             .iter()
-            .filter(|x| x.time_left() > 0)
-            .map(|x| x.clone())
+            .filter(|x| x.is_expired())
+            .filter_map(|x| x.kind.decal().map(|decal| (x.pos, decal)))
             .collect();
 
-        let delta = self.prev_process.elapsed().as_millis() as f64;
-        self.prev_process = Instant::now();
-
-        for p in &mut self.particles {
-            p.error.0 += p.speed.0 * delta;
-            p.error.1 += p.speed.1 * delta;
+        self.particles.retain(|x| !x.is_expired());
 
-            if p.error.0.abs() >= 0.5 {
-                p.pos.x += p.error.0.signum() as i32;
-                p.error.0 -= p.error.0.signum();
-            }
+        let delta = world.animation_clock.delta_ms();
 
-            if p.error.1.abs() >= 0.5 {
-                p.pos.y += p.error.1.signum() as i32;
-                p.error.1 -= p.error.1.signum();
+        for p in &mut self.particles {
+            if let Some(wall) = p.step(&world.field, delta) {
+                decals.extend(p.kind.decal().map(|decal| (wall, decal)));
             }
         }
+
+        self.particles.retain(|x| !x.splattered);
+
+        decals
     }
 }
 
-impl Draw for BloodParticle {
+impl Draw for Particle {
     fn draw(&self, ctx: &mut BTerm, pos: Point) {
-        let time_left = self.time_left();
+        let age = self.age();
+        let glyphs = self.kind.glyphs();
+        let i = ((age * glyphs.len() as f32) as usize).min(glyphs.len() - 1);
 
-        let ch = if time_left > 250 { '█' } else { '▒' };
+        let (fresh, faded) = self.kind.colors();
+        let color = fresh.lerp(faded, age);
 
-        ctx.print_color(pos.x, pos.y, COLOR_BLOOD, COLOR_BG, ch);
+        ctx.print_color(pos.x, pos.y, color, palette().bg, glyphs[i]);
     }
 }
 
-impl Draw for BloodParticlesEffect {
-    fn draw(&self, ctx: &mut BTerm, pos: Point) {
+impl DrawWithFov for ParticleSystem {
+    /// Prints particles in visible cells only, the freshest one of a cell is on top
+    fn draw_with_fov(&self, ctx: &mut BTerm, fov: &Fov, pos: Point, fov_pos: Point) {
+        let mut on_top: HashMap<Point, &Particle> = HashMap::new();
+
         for p in &self.particles {
+            if !fov.contains(&(p.pos + fov_pos)) {
+                continue;
+            }
+
+            match on_top.get(&p.pos) {
+                Some(top) if top.age() <= p.age() => (),
+                _ => {
+                    on_top.insert(p.pos, p);
+                }
+            }
+        }
+
+        for p in on_top.values() {
             p.draw(ctx, p.pos + pos);
         }
     }
@@ -1,67 +1,802 @@
-use crate::colors::{COLOR_BG, COLOR_PLAYER};
-use crate::draw::{BTerm, Draw, Point};
-use crate::field::FieldPosition;
+use crate::action::Action;
+use crate::archetype::{Ability, Archetype};
+use crate::colors::palette;
+use crate::combat::{roll, Blow, CombatRules, CombatStats, Health};
+use crate::direction::Direction;
+use crate::draw::{BTerm, DrawLit, Fov, Point};
+use crate::experience::{kill_experience, level_experience, Advance, Perk};
+use crate::field::{Field, FieldPosition};
+use crate::item::{Inventory, ItemKind};
+use crate::keymap::Command;
+use crate::light::{LightKind, LightSource};
+use crate::particles::ParticleKind;
+use crate::projectile::{Projectile, RangedAttack};
 use crate::state::{State, Stepper, StepperStatus};
-use bracket_terminal::prelude::VirtualKeyCode;
+use crate::status::{StatusKind, Statuses, BLIND_RADIUS};
+use crate::terrain::FieldCell;
+use bracket_pathfinding::prelude::DistanceAlg;
 
-const MOVE_TIME: f64 = 1.0;
+/// Turns of digging through a wall
+const DIG_TURNS: u32 = 6;
+/// Hidden traps are searched for up to this distance
+const SEARCH_RADIUS: f32 = 3.0;
+const SEARCH_PROB: f64 = 0.6;
+/// Every turn there is a chance to notice a hidden trap next to the player
+const NOTICE_RADIUS: f32 = 1.5;
+const NOTICE_PROB: f64 = 0.15;
+const REST_TURNS: u32 = 100;
+/// Lantern fuel is measured in turns of burning
+const START_FUEL: f64 = 500.0;
+const MAX_FUEL: f64 = 1000.0;
+const FLASK_FUEL: f64 = 300.0;
+/// The lantern starts to dim when there is less fuel left
+const LOW_FUEL: f64 = 100.0;
+const MIN_LIGHT_RADIUS: i32 = 2;
+/// Load the player carries without slowing down
+const CARRY_WEIGHT: f64 = 15.0;
+const PACK_MULE_WEIGHT: f64 = 10.0;
+/// Time before the ability can be used again
+const ABILITY_COOLDOWN: f64 = 50.0;
+const BATTLE_CRY_STUN: f64 = 3.0;
+const SPRINT_TIME: f64 = 10.0;
+const SMOKE_RADIUS: f32 = 3.0;
+const SMOKE_BLIND_TIME: f64 = 8.0;
+/// Growth of stats chosen on a level up
+const ATTACK_ADVANCE: f64 = 1.25;
+const DEFENCE_ADVANCE: f64 = 1.25;
+const LUCK_ADVANCE: f64 = 0.1;
+const DAMAGE_ADVANCE: u32 = 1;
+const HP_ADVANCE: u32 = 5;
+
+/// Action repeated by the player every turn until it is finished or interrupted
+enum Activity {
+    Rest {
+        turns_left: u32,
+    },
+    Explore,
+    Travel {
+        path: Vec<Point>,
+    },
+    Dig {
+        target: Point,
+        turns_left: u32,
+    },
+    /// Rest until health points are restored
+    Heal,
+}
 
 pub struct Player {
     pos: Point,
     clock: f64,
+    pub archetype: Archetype,
     pub view_radius: i32,
+    activity: Option<Activity>,
+    pub stats: CombatStats,
+    pub health: Health,
+    pub statuses: Statuses,
+    pub inventory: Inventory,
+    /// Turns the lantern can burn
+    pub fuel: f64,
+    /// Share of the lantern light left, it fades out when the player dies
+    pub light_scale: f64,
+    dead: bool,
+    /// Ranged attack chosen in the targeting mode
+    aimed: Option<(RangedAttack, Point)>,
+    /// Wall chosen to dig through
+    dig_target: Option<Point>,
+    /// Tool or weapon in the hands, taking another one takes time
+    wielded: Option<ItemKind>,
+    pub level: u32,
+    pub experience: u32,
+    pub perks: Vec<Perk>,
+    /// Level ups waiting for the player to choose an advance
+    advances: u32,
+    /// Clock when the ability can be used again
+    ability_clock: f64,
 }
 
 impl Player {
-    pub fn new(pos: Point, view_radius: i32) -> Player {
+    pub fn new(pos: Point, archetype: Archetype) -> Player {
+        let stats = archetype.stats();
+
         Player {
             pos,
             clock: 0.0,
-            view_radius,
+            archetype,
+            view_radius: archetype.view_radius(),
+            activity: None,
+            stats,
+            health: Health::new(stats.max_hp),
+            statuses: Statuses::default(),
+            inventory: archetype.inventory(),
+            fuel: START_FUEL,
+            light_scale: 1.0,
+            dead: false,
+            aimed: None,
+            dig_target: None,
+            wielded: None,
+            level: 1,
+            experience: 0,
+            perks: Vec::new(),
+            advances: 0,
+            ability_clock: 0.0,
+        }
+    }
+
+    /// Light of the lantern, it dims when the fuel is running low
+    pub fn light(&self) -> LightSource {
+        let light = LightSource::new(LightKind::Carried, self.pos);
+
+        let radius = if self.fuel <= 0.0 {
+            0
+        } else if self.fuel >= LOW_FUEL {
+            light.radius
+        } else {
+            let dimmed = (light.radius - MIN_LIGHT_RADIUS) as f64 * self.fuel / LOW_FUEL;
+            MIN_LIGHT_RADIUS + dimmed.ceil() as i32
+        };
+
+        light.with_radius((radius as f64 * self.light_scale).round() as i32)
+    }
+
+    /// Radius of the field of view, blindness narrows it
+    pub fn sight_radius(&self) -> i32 {
+        if self.statuses.has(StatusKind::Blindness) {
+            BLIND_RADIUS
+        } else {
+            self.view_radius
+        }
+    }
+
+    /// Walking time multiplier from the load beyond the carried weight
+    pub fn encumbrance(&self) -> f64 {
+        let carried = if self.perks.contains(&Perk::PackMule) {
+            CARRY_WEIGHT + PACK_MULE_WEIGHT
+        } else {
+            CARRY_WEIGHT
+        };
+
+        f64::max(self.inventory.weight() / carried, 1.0)
+    }
+
+    /// Gain experience for a kill, every new level brings an advance to choose
+    pub fn gain_experience(&mut self, experience: u32) {
+        self.experience += experience;
+
+        while self.experience >= level_experience(self.level + 1) {
+            self.level += 1;
+            self.advances += 1;
         }
     }
 
-    fn action(&mut self, world: &State, direction: (i32, i32)) -> StepperStatus {
-        let next_pos = self.pos + Point::from(direction);
+    pub fn has_advances(&self) -> bool {
+        self.advances > 0
+    }
+
+    /// Level which brought the next advance to choose
+    pub fn advance_level(&self) -> u32 {
+        self.level + 1 - self.advances
+    }
+
+    /// Apply the advance chosen on a level up
+    pub fn advance(&mut self, advance: Advance) {
+        match advance {
+            Advance::Attack => {
+                self.stats.attack *= ATTACK_ADVANCE;
+                self.stats.damage.min += DAMAGE_ADVANCE;
+                self.stats.damage.max += DAMAGE_ADVANCE;
+            }
+            Advance::Defence => {
+                self.stats.defence *= DEFENCE_ADVANCE;
+                self.stats.max_hp += HP_ADVANCE;
+                self.health.raise(HP_ADVANCE);
+            }
+            Advance::Luck => self.stats.luck += LUCK_ADVANCE,
+            Advance::Perk(perk) => self.perks.push(perk),
+        }
+
+        self.advances = self.advances.saturating_sub(1);
+    }
+
+    /// Chance multiplier to find hidden traps
+    fn trap_finding(&self) -> f64 {
+        let eyes = if self.perks.contains(&Perk::KeenEyes) {
+            2.0
+        } else {
+            1.0
+        };
+
+        eyes * (1.0 + self.stats.luck)
+    }
+
+    /// Stats weakened by the statuses
+    pub fn combat_stats(&self) -> CombatStats {
+        self.statuses.apply(self.stats)
+    }
+
+    pub fn kill(&mut self) {
+        self.dead = true;
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.dead
+    }
+
+    pub fn teleport(&mut self, pos: Point) {
+        self.pos = pos;
+    }
+
+    /// Lose time, like when caught in a net
+    pub fn delay(&mut self, time: f64) {
+        self.clock += time;
+    }
+
+    /// Stop the current activity, something has happened
+    pub fn interrupt(&mut self) {
+        self.activity = None;
+    }
+
+    /// Let the time of the action pass
+    fn spend(&mut self, action: Action) {
+        let walk_scale = self.encumbrance() * self.archetype.stride();
+        self.clock += action.time(&self.statuses, walk_scale);
+    }
+
+    /// Time left before the ability can be used again
+    pub fn ability_cooldown(&self) -> f64 {
+        f64::max(self.ability_clock - self.clock, 0.0)
+    }
 
-        if world.field.is_wall(next_pos.x, next_pos.y) {
+    fn action_ability(&mut self, world: &State) -> StepperStatus {
+        let ability = self.archetype.ability();
+
+        if self.ability_cooldown() > 0.0 {
+            world
+                .log
+                .borrow_mut()
+                .add(format!("You are not ready to use {} yet.", ability.name()));
             return StepperStatus::Pending;
         }
 
-        let enemy = world
-            .enemies
+        match ability {
+            Ability::BattleCry => {
+                world.log.borrow_mut().add("You let out a battle cry!");
+
+                for enemy in &world.enemies {
+                    let mut enemy = enemy.borrow_mut();
+
+                    if DistanceAlg::Chebyshev.distance2d(enemy.pos(), self.pos) <= 1.0 {
+                        enemy.statuses.add(StatusKind::Stun, BATTLE_CRY_STUN);
+                    }
+                }
+            }
+            Ability::Sprint => {
+                world.log.borrow_mut().add("You break into a sprint.");
+                self.statuses.add(StatusKind::Haste, SPRINT_TIME);
+            }
+            Ability::SmokeBomb => {
+                world.log.borrow_mut().add("You throw a smoke bomb.");
+                world
+                    .particles
+                    .borrow_mut()
+                    .burst(ParticleKind::Smoke, self.pos, 12, 0.5);
+
+                for enemy in &world.enemies {
+                    let mut enemy = enemy.borrow_mut();
+
+                    if DistanceAlg::Pythagoras.distance2d(enemy.pos(), self.pos) <= SMOKE_RADIUS {
+                        enemy.statuses.add(StatusKind::Blindness, SMOKE_BLIND_TIME);
+                    }
+                }
+            }
+        }
+
+        self.spend(Action::Ability);
+        self.ability_clock = self.clock + ABILITY_COOLDOWN;
+
+        StepperStatus::Finished
+    }
+
+    /// Take the item into the hands unless it is there already
+    fn wield(&mut self, world: &State, kind: ItemKind) {
+        if self.wielded == Some(kind) {
+            return;
+        }
+
+        self.wielded = Some(kind);
+        self.spend(Action::Equip);
+        world
+            .log
+            .borrow_mut()
+            .add(format!("You ready the {}.", kind.name()));
+    }
+
+    fn burn_fuel(&mut self, world: &State, time: f64) {
+        if self.fuel <= 0.0 {
+            return;
+        }
+
+        let was_low = self.fuel < LOW_FUEL;
+        let burnt = if self.perks.contains(&Perk::Lamplighter) {
+            time / 2.0
+        } else {
+            time
+        };
+        self.fuel = f64::max(self.fuel - burnt, 0.0);
+
+        if self.fuel <= 0.0 {
+            world.log.borrow_mut().add("Your lantern goes out.");
+            world
+                .particles
+                .borrow_mut()
+                .burst(ParticleKind::Smoke, self.pos, 4, 0.2);
+        } else if !was_low && self.fuel < LOW_FUEL {
+            world.log.borrow_mut().add("Your lantern flickers.");
+        }
+    }
+
+    fn action_refuel(&mut self, world: &State) -> StepperStatus {
+        if self.fuel >= MAX_FUEL || !self.inventory.take(ItemKind::OilFlask) {
+            return StepperStatus::Pending;
+        }
+
+        self.fuel = f64::min(self.fuel + FLASK_FUEL, MAX_FUEL);
+        world.log.borrow_mut().add("You refuel the lantern.");
+        world
+            .particles
+            .borrow_mut()
+            .burst(ParticleKind::Smoke, self.pos, 2, 0.2);
+        self.spend(Action::Refuel);
+
+        StepperStatus::Finished
+    }
+
+    /// Open a closed door or unlock a locked one with a key
+    fn action_open(&mut self, world: &State, door: Point) -> StepperStatus {
+        let mut log = world.log.borrow_mut();
+
+        match world.field.cell(door) {
+            Some(FieldCell::ClosedDoor) => log.add("You open the door."),
+            Some(FieldCell::LockedDoor) if self.inventory.take(ItemKind::Key) => {
+                log.add("You unlock the door.")
+            }
+            Some(FieldCell::LockedDoor) => {
+                log.add("The door is locked.");
+                return StepperStatus::Pending;
+            }
+            _ => return StepperStatus::Pending,
+        }
+
+        world.change_terrain(door, FieldCell::OpenDoor);
+        self.spend(Action::Door);
+
+        StepperStatus::Finished
+    }
+
+    /// Close an open door next to the player if nothing stands in it
+    fn action_close(&mut self, world: &State) -> StepperStatus {
+        let door = Direction::ALL
             .iter()
-            .filter(|x| x.borrow().pos() == next_pos)
-            .next();
+            .map(|d| self.pos + d.delta())
+            .find(|p| {
+                world.field.cell(*p) == Some(FieldCell::OpenDoor)
+                    && world.enemy_at(*p).is_none()
+                    && !world.items.borrow().iter().any(|i| i.pos == *p)
+            });
 
-        if let Some(_) = enemy {
-            world.blood_effect.borrow_mut().spawn(
-                next_pos,
-                (direction.0 as f64, direction.1 as f64),
-                2,
-                0.6,
-            );
+        match door {
+            Some(door) => {
+                world.change_terrain(door, FieldCell::ClosedDoor);
+                world.log.borrow_mut().add("You close the door.");
+                self.spend(Action::Door);
 
-            self.clock += MOVE_TIME;
+                StepperStatus::Finished
+            }
+            None => {
+                world
+                    .log
+                    .borrow_mut()
+                    .add("There is no open door to close.");
+
+                StepperStatus::Pending
+            }
+        }
+    }
+
+    /// Let the statuses wear off, poison and bleeding may kill under the health rules
+    fn tick_statuses(&mut self, world: &State, time: f64) {
+        let drain = self.statuses.tick(time);
+
+        if world.rules != CombatRules::Health || self.dead {
+            return;
+        }
+
+        self.health.drain(drain);
+
+        if self.health.is_empty() {
+            self.kill();
+            world.log.borrow_mut().add("You succumb to your wounds.");
+        }
+    }
+
+    /// Look for hidden traps around, luck makes finding them easier
+    fn action_search(&mut self, world: &State) -> StepperStatus {
+        let found: Vec<_> = world
+            .field
+            .hidden_traps(self.pos, SEARCH_RADIUS)
+            .into_iter()
+            .filter(|t| world.can_see(t.pos) && roll(SEARCH_PROB * self.trap_finding()))
+            .collect();
+
+        let mut log = world.log.borrow_mut();
+
+        if found.is_empty() {
+            log.add("You find nothing.");
+        }
+
+        for trap in found {
+            trap.reveal();
+            log.add(format!("You find a {}.", trap.kind.name()));
+        }
+
+        self.spend(Action::Search);
+
+        StepperStatus::Finished
+    }
+
+    /// Passive chance to notice hidden traps next to the player
+    fn notice_traps(&mut self, world: &State) {
+        for trap in world.field.hidden_traps(self.pos, NOTICE_RADIUS) {
+            if world.can_see(trap.pos) && roll(NOTICE_PROB * self.trap_finding()) {
+                trap.reveal();
+                world
+                    .log
+                    .borrow_mut()
+                    .add(format!("You notice a {}.", trap.kind.name()));
+                self.interrupt();
+            }
+        }
+    }
+
+    /// Check if the player has everything for the ranged attack
+    pub fn can_attack(&self, attack: RangedAttack) -> bool {
+        let has_launcher = match attack.launcher() {
+            Some(launcher) => self.inventory.count(launcher) > 0,
+            None => true,
+        };
+
+        has_launcher && self.inventory.count(attack.ammo()) > 0
+    }
+
+    /// Make the ranged attack on the next turn
+    pub fn aim(&mut self, attack: RangedAttack, target: Point) {
+        self.aimed = Some((attack, target));
+    }
+
+    /// Start digging through the wall on the next turn
+    pub fn dig(&mut self, target: Point) {
+        self.dig_target = Some(target);
+    }
+
+    fn start_dig(&mut self, world: &State, target: Point) -> StepperStatus {
+        if self.inventory.count(ItemKind::Pickaxe) == 0 {
+            return StepperStatus::Pending;
+        }
+
+        if !world.field.is_diggable(target) {
+            world.log.borrow_mut().add("You can not dig there.");
+            return StepperStatus::Pending;
+        }
+
+        world.log.borrow_mut().add("You start digging.");
+        self.start_activity(
+            world,
+            Activity::Dig {
+                target,
+                turns_left: DIG_TURNS,
+            },
+        )
+    }
+
+    fn action_ranged(
+        &mut self,
+        world: &State,
+        attack: RangedAttack,
+        target: Point,
+    ) -> StepperStatus {
+        if !self.can_attack(attack) || target == self.pos {
+            return StepperStatus::Pending;
+        }
+
+        if let Some(launcher) = attack.launcher() {
+            self.wield(world, launcher);
+        }
+
+        self.inventory.take(attack.ammo());
+
+        let projectile = Projectile::launch(world, self.pos, target, attack, &self.combat_stats());
+        world.projectiles.borrow_mut().push(projectile);
+
+        self.spend(Action::Shoot);
+
+        StepperStatus::Finished
+    }
+
+    fn action_pick_up(&mut self, world: &State) -> StepperStatus {
+        let mut items = world.items.borrow_mut();
+        let (here, rest): (Vec<_>, Vec<_>) = items.drain(..).partition(|i| i.pos == self.pos);
+        *items = rest;
+
+        if here.is_empty() {
+            return StepperStatus::Pending;
+        }
+
+        for item in here {
+            world
+                .log
+                .borrow_mut()
+                .add(format!("You pick up {}.", item.description()));
+            self.inventory.add(item.kind, item.count);
+        }
+
+        self.spend(Action::PickUp);
+
+        StepperStatus::Finished
+    }
+
+    fn attack(&mut self, world: &State, direction: Direction) {
+        let target = self.pos + direction.delta();
+
+        let enemy = match world.enemy_at(target) {
+            Some(enemy) => enemy,
+            None => return,
+        };
+
+        world
+            .particles
+            .borrow_mut()
+            .spawn(ParticleKind::Blood, target, direction.unit(), 2, 0.6);
+
+        let mut enemy = enemy.borrow_mut();
+
+        let mut animations = world.animations.borrow_mut();
+        animations.lunge(&world.fov, self.pos, target, '@', palette().player);
+        animations.hit_flash(&world.fov, target);
+
+        let blow = Blow::roll(
+            world.rules,
+            &self.combat_stats(),
+            &enemy.combat_stats(),
+            &enemy.health,
+        );
+
+        if blow.kill {
+            enemy.kill();
+            self.gain_experience(kill_experience(&enemy.stats));
+
+            let (glyph, color) = enemy.appearance();
+            animations.death_fade(&world.fov, target, glyph, color);
+            world
+                .log
+                .borrow_mut()
+                .add(format!("You kill the {}.", enemy.name));
+        } else {
+            enemy.health.wound(blow.damage);
+            world
+                .log
+                .borrow_mut()
+                .add(format!("You hit the {}.", enemy.name));
+        }
+
+        if let Some(affliction) = blow.affliction {
+            enemy.statuses.add(affliction.kind, affliction.duration);
+        }
+    }
+
+    fn action_wait(&mut self) -> StepperStatus {
+        self.spend(Action::Rest);
+        StepperStatus::Finished
+    }
+
+    /// Rest for a while, or until healed when blows take health points
+    fn action_rest(&mut self, world: &State) -> StepperStatus {
+        match world.rules {
+            CombatRules::Chance => self.start_activity(
+                world,
+                Activity::Rest {
+                    turns_left: REST_TURNS,
+                },
+            ),
+            CombatRules::Health if self.health.is_full() => {
+                world.log.borrow_mut().add("You are not hurt.");
+                StepperStatus::Pending
+            }
+            CombatRules::Health => self.start_activity(world, Activity::Heal),
+        }
+    }
+
+    /// Start an activity, it can not be started while enemies are seen
+    fn start_activity(&mut self, world: &State, activity: Activity) -> StepperStatus {
+        if world.enemy_in_view() {
+            return StepperStatus::Pending;
+        }
+
+        self.continue_activity(world, activity)
+    }
+
+    /// Make a step of the activity, the activity is kept if it is not finished
+    fn continue_activity(&mut self, world: &State, activity: Activity) -> StepperStatus {
+        match activity {
+            Activity::Rest { turns_left } => {
+                self.activity = match turns_left {
+                    0 | 1 => None,
+                    _ => Some(Activity::Rest {
+                        turns_left: turns_left - 1,
+                    }),
+                };
+                self.action_wait()
+            }
+            Activity::Explore => match world.field.explore_step(self.pos) {
+                Some(direction) => self.activity_move(world, direction, Activity::Explore),
+                None => StepperStatus::Pending,
+            },
+            Activity::Heal => {
+                let status = self.action_wait();
+
+                if !self.health.is_full() {
+                    self.activity = Some(Activity::Heal);
+                }
+
+                status
+            }
+            Activity::Dig { target, turns_left } => {
+                let (x, y) = Direction::from_delta(target - self.pos)
+                    .map(|d| d.unit())
+                    .unwrap_or((0.0, 0.0));
+                world
+                    .particles
+                    .borrow_mut()
+                    .spawn(ParticleKind::Dust, self.pos, (-x, -y), 2, 0.4);
+                self.wield(world, ItemKind::Pickaxe);
+                self.spend(Action::Dig);
+
+                if turns_left > 1 {
+                    self.activity = Some(Activity::Dig {
+                        target,
+                        turns_left: turns_left - 1,
+                    });
+                } else {
+                    world.change_terrain(target, FieldCell::Empty);
+                    world.log.borrow_mut().add("You dig through the wall.");
+                }
+
+                StepperStatus::Finished
+            }
+            Activity::Travel { mut path } => {
+                if path.is_empty() {
+                    return StepperStatus::Pending;
+                }
+
+                let next = path.remove(0);
+
+                match Direction::from_delta(next - self.pos) {
+                    Some(direction) => {
+                        self.activity_move(world, direction, Activity::Travel { path })
+                    }
+                    None => StepperStatus::Pending,
+                }
+            }
+        }
+    }
+
+    /// Move as a part of the activity, the activity stops if the move is not possible
+    fn activity_move(
+        &mut self,
+        world: &State,
+        direction: Direction,
+        activity: Activity,
+    ) -> StepperStatus {
+        let status = self.action(world, direction);
+
+        if let StepperStatus::Finished = status {
+            self.activity = Some(activity);
+        }
+
+        status
+    }
+
+    fn action(&mut self, world: &State, direction: Direction) -> StepperStatus {
+        let next_pos = self.pos + direction.delta();
+
+        if let Some(FieldCell::ClosedDoor) | Some(FieldCell::LockedDoor) =
+            world.field.cell(next_pos)
+        {
+            return self.action_open(world, next_pos);
+        }
+
+        if !world.field.can_step(self.pos, direction) {
+            return StepperStatus::Pending;
+        }
+
+        if world.enemy_at(next_pos).is_some() {
+            self.attack(world, direction);
+            self.spend(Action::Attack);
 
             return StepperStatus::Finished;
         }
 
+        let (x, y) = direction.unit();
+        world
+            .particles
+            .borrow_mut()
+            .spawn(ParticleKind::Dust, self.pos, (-x, -y), 1, 0.3);
+
         self.pos = next_pos;
-        self.clock += MOVE_TIME;
+        self.spend(Action::Move(world.field.move_cost(next_pos)));
+
+        if world.field.trap_at(self.pos).is_some() {
+            world.trigger_trap(self.pos);
+        }
+
+        if let Some(LightKind::GlowingMoss) = world.field.light_at(self.pos).map(|l| l.kind) {
+            world
+                .particles
+                .borrow_mut()
+                .burst(ParticleKind::Magic, self.pos, 6, 0.4);
+        }
 
         StepperStatus::Finished
     }
 
-    fn process_key(&mut self, world: &State, key: VirtualKeyCode) -> StepperStatus {
-        match key {
-            VirtualKeyCode::W => self.action(world, (0, -1)),
-            VirtualKeyCode::A => self.action(world, (-1, 0)),
-            VirtualKeyCode::S => self.action(world, (0, 1)),
-            VirtualKeyCode::D => self.action(world, (1, 0)),
+    fn process_command(&mut self, world: &State, command: Command) -> StepperStatus {
+        match command {
+            Command::Move(direction) => self.action(world, direction),
+            Command::Wait => self.action_wait(),
+            Command::Rest => self.action_rest(world),
+            Command::Explore => self.start_activity(world, Activity::Explore),
+            Command::PickUp => self.action_pick_up(world),
+            Command::Refuel => self.action_refuel(world),
+            Command::Close => self.action_close(world),
+            Command::Search => self.action_search(world),
+            Command::Ability => self.action_ability(world),
             _ => StepperStatus::Pending,
         }
     }
+
+    /// Make the chosen action, continue the activity or wait for input
+    fn process_turn(&mut self, world: &State, ctx: &BTerm) -> StepperStatus {
+        if self.statuses.has(StatusKind::Stun) {
+            return self.action_wait();
+        }
+
+        if let Some((attack, target)) = self.aimed.take() {
+            return self.action_ranged(world, attack, target);
+        }
+
+        if let Some(target) = self.dig_target.take() {
+            return self.start_dig(world, target);
+        }
+
+        if let Some(activity) = self.activity.take() {
+            if ctx.key.is_some() || ctx.left_click || world.enemy_in_view() {
+                return StepperStatus::Pending;
+            }
+
+            return self.continue_activity(world, activity);
+        }
+
+        if ctx.left_click {
+            return match world.field.travel_path(self.pos, ctx.mouse_point()) {
+                Some(path) => self.start_activity(world, Activity::Travel { path }),
+                None => StepperStatus::Pending,
+            };
+        }
+
+        match world.command(ctx) {
+            None => StepperStatus::Pending,
+            Some(command) => self.process_command(world, command),
+        }
+    }
 }
 
 impl FieldPosition for Player {
@@ -70,9 +805,12 @@ impl FieldPosition for Player {
     }
 }
 
-impl Draw for Player {
-    fn draw(&self, ctx: &mut BTerm, pos: Point) {
-        ctx.print_color(pos.x, pos.y, COLOR_PLAYER, COLOR_BG, "@")
+impl DrawLit for Player {
+    fn draw_lit(&self, ctx: &mut BTerm, fov: &Fov, field: &Field, pos: Point) {
+        if fov.contains(&self.pos) {
+            let color = field.tint(palette().player, self.pos);
+            ctx.print_color(pos.x, pos.y, color, palette().bg, "@")
+        }
     }
 }
 
@@ -82,9 +820,16 @@ impl Stepper for Player {
     }
 
     fn process(&mut self, world: &State, ctx: &BTerm) -> StepperStatus {
-        match ctx.key {
-            None => StepperStatus::Pending,
-            Some(key) => self.process_key(world, key),
+        let clock = self.clock;
+        let status = self.process_turn(world, ctx);
+        self.burn_fuel(world, self.clock - clock);
+        self.health.regenerate(self.clock - clock);
+        self.tick_statuses(world, self.clock - clock);
+
+        if let StepperStatus::Finished = status {
+            self.notice_traps(world);
         }
+
+        status
     }
 }
@@ -0,0 +1,192 @@
+use crate::colors::palette;
+use crate::combat::{roll, Blow, CombatStats};
+use crate::draw::{BTerm, Draw, Point};
+use crate::enemy::Enemy;
+use crate::field::FieldPosition;
+use crate::item::ItemKind;
+use crate::state::State;
+use crate::status::Affliction;
+use bracket_pathfinding::prelude::{line2d, DistanceAlg, LineAlg};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Time for a projectile to fly through one cell
+const CELL_FLIGHT_MS: f64 = 25.0;
+
+#[derive(Clone, Copy)]
+pub enum RangedAttack {
+    Fire,
+    Throw,
+}
+
+impl RangedAttack {
+    pub fn ammo(self) -> ItemKind {
+        match self {
+            Self::Fire => ItemKind::Arrow,
+            Self::Throw => ItemKind::ThrowingKnife,
+        }
+    }
+
+    /// Item needed to launch the ammo
+    pub fn launcher(self) -> Option<ItemKind> {
+        match self {
+            Self::Fire => Some(ItemKind::Bow),
+            Self::Throw => None,
+        }
+    }
+
+    fn power(self) -> f64 {
+        match self {
+            Self::Fire => 1.0,
+            Self::Throw => 0.8,
+        }
+    }
+
+    fn range(self) -> usize {
+        match self {
+            Self::Fire => 12,
+            Self::Throw => 6,
+        }
+    }
+
+    /// Probability for the ammo to break when it hits an enemy
+    fn break_prob(self) -> f64 {
+        match self {
+            Self::Fire => 0.5,
+            Self::Throw => 0.1,
+        }
+    }
+}
+
+/// Flying arrow or knife. The outcome is rolled at launch
+/// and applied to the world when the flight animation is over
+pub struct Projectile {
+    pub kind: ItemKind,
+    from: Point,
+    path: Vec<Point>,
+    elapsed_ms: f64,
+    pub hit: Option<Rc<RefCell<Enemy>>>,
+    pub kill: bool,
+    /// Health points taken from the enemy if it survives
+    pub damage: u32,
+    /// Status left on the enemy if it survives
+    pub affliction: Option<Affliction>,
+    pub breaks: bool,
+    pub missed: Option<&'static str>,
+    /// The flight is stopped by a wall
+    pub hit_wall: bool,
+}
+
+impl Projectile {
+    /// Trace the projectile flight to the target. It stops before walls,
+    /// at the first enemy it hits or at the target cell
+    pub fn launch(
+        world: &State,
+        from: Point,
+        target: Point,
+        attack: RangedAttack,
+        stats: &CombatStats,
+    ) -> Projectile {
+        let mut projectile = Projectile {
+            kind: attack.ammo(),
+            from,
+            path: Vec::new(),
+            elapsed_ms: 0.0,
+            hit: None,
+            kill: false,
+            damage: 0,
+            affliction: None,
+            breaks: false,
+            missed: None,
+            hit_wall: false,
+        };
+
+        for p in line2d(LineAlg::Bresenham, from, target)
+            .into_iter()
+            .skip(1)
+            .take(attack.range())
+        {
+            if world.field.is_solid(p) {
+                projectile.hit_wall = true;
+                break;
+            }
+
+            projectile.path.push(p);
+
+            if let Some(enemy) = world.enemy_at(p) {
+                let enemy_stats = enemy.borrow().combat_stats();
+                let distance = DistanceAlg::Pythagoras.distance2d(from, p) as f64;
+
+                if roll(stats.hit_prob(&enemy_stats, distance)) {
+                    let blow = Blow::roll(
+                        world.rules,
+                        &stats.with_power(attack.power()),
+                        &enemy_stats,
+                        &enemy.borrow().health,
+                    );
+
+                    projectile.kill = blow.kill;
+                    projectile.damage = blow.damage;
+                    projectile.affliction = blow.affliction;
+                    projectile.breaks = roll(attack.break_prob());
+                    projectile.hit = Some(enemy);
+                    break;
+                }
+
+                projectile.missed = Some(enemy.borrow().name);
+            }
+        }
+
+        projectile
+    }
+
+    pub fn update(&mut self, delta_ms: f64) {
+        self.elapsed_ms += delta_ms;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed_ms >= CELL_FLIGHT_MS * self.path.len() as f64
+    }
+
+    /// Cell where the projectile stops
+    pub fn end(&self) -> Point {
+        match &self.hit {
+            Some(enemy) => enemy.borrow().pos(),
+            None => self.path.last().copied().unwrap_or(self.from),
+        }
+    }
+
+    /// Unit vector of the flight direction
+    pub fn direction(&self) -> (f64, f64) {
+        let delta = self.end() - self.from;
+        let (x, y) = (delta.x as f64, delta.y as f64);
+        let l = (x * x + y * y).sqrt().max(1.0);
+        (x / l, y / l)
+    }
+
+    fn glyph(&self) -> char {
+        let (x, y) = self.direction();
+
+        match self.kind {
+            ItemKind::ThrowingKnife => '*',
+            _ if x.abs() > 2.0 * y.abs() => '-',
+            _ if y.abs() > 2.0 * x.abs() => '|',
+            _ if x * y > 0.0 => '\\',
+            _ => '/',
+        }
+    }
+
+    fn current_cell(&self) -> Option<Point> {
+        let i = (self.elapsed_ms / CELL_FLIGHT_MS) as usize;
+        self.path.get(i).copied()
+    }
+}
+
+impl Draw for Projectile {
+    fn draw(&self, ctx: &mut BTerm, pos: Point) {
+        if let Some(p) = self.current_cell() {
+            let p = p + pos;
+            ctx.print_color(p.x, p.y, palette().item, palette().bg, self.glyph());
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::combat::CombatRules;
+use crate::config::{read_entries, ConfigEntry, ConfigError, ConfigResult};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Game options
+#[derive(Clone)]
+pub struct Settings {
+    /// Speed of animations, 1 is the normal speed
+    pub time_scale: f64,
+    /// Show attack and death animations, the game waits for them
+    pub animations: bool,
+    /// Name of a built-in color theme or a path to a palette file
+    pub theme: String,
+    /// Generate caves half as wide and stretch them to look round in tall cells
+    pub stretch: bool,
+    /// Blows kill by chance or deal damage to health points
+    pub combat: CombatRules,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            time_scale: 1.0,
+            animations: true,
+            theme: "default".to_string(),
+            stretch: false,
+            combat: CombatRules::Chance,
+        }
+    }
+}
+
+impl Settings {
+    /// Load settings from a file of `option = value` lines,
+    /// missing options keep default values
+    pub fn load(path: &Path) -> ConfigResult<Settings> {
+        let mut settings = Settings::default();
+
+        let entries = match read_entries(path)? {
+            Some(entries) => entries,
+            None => return Ok(settings),
+        };
+
+        for entry in entries {
+            match entry.key.as_str() {
+                "time_scale" => {
+                    let time_scale: f64 = parse(path, &entry)?;
+
+                    // animations never finish when their time stands still or runs back
+                    if !time_scale.is_finite() || time_scale <= 0.0 {
+                        let message = format!(
+                            "`time_scale` must be a finite number above 0, got `{}`",
+                            entry.value
+                        );
+                        return Err(ConfigError::new(path, entry.line, message).into());
+                    }
+
+                    settings.time_scale = time_scale;
+                }
+                "animations" => settings.animations = parse(path, &entry)?,
+                "theme" => settings.theme = entry.value,
+                "stretch" => settings.stretch = parse(path, &entry)?,
+                "combat" => settings.combat = parse(path, &entry)?,
+                _ => {
+                    let message = format!("unknown option `{}`", entry.key);
+                    return Err(ConfigError::new(path, entry.line, message).into());
+                }
+            }
+        }
+
+        Ok(settings)
+    }
+}
+
+fn parse<T: FromStr>(path: &Path, entry: &ConfigEntry) -> ConfigResult<T> {
+    entry.value.parse().map_err(|_| {
+        let message = format!("invalid value `{}` for `{}`", entry.value, entry.key);
+        ConfigError::new(path, entry.line, message).into()
+    })
+}
@@ -1,15 +1,36 @@
-use crate::draw::{Draw, DrawWithFov};
+use crate::animation::{AnimationClock, Animations};
+use crate::archetype::Archetype;
+use crate::colors::{palette, Themes};
+use crate::combat::{Blow, CombatRules};
+use crate::direction::Direction;
+use crate::draw::{Draw, DrawLit, DrawWithFov};
 use crate::enemy::{Enemy, EnemyBuilder};
+use crate::experience::{kill_experience, Advance};
 use crate::field::{Field, FieldPosition};
-use crate::particles::BloodParticlesEffect;
+use crate::game_over::GameSummary;
+use crate::item::{drop_item, FloorItem, ItemKind};
+use crate::keymap::{key_name, Command, Keymap};
+use crate::light::{LightSource, MIN_VISIBLE_LIGHT};
+use crate::log::Log;
+use crate::particles::{ParticleKind, ParticleSystem};
 use crate::player::Player;
+use crate::projectile::{Projectile, RangedAttack};
+use crate::settings::Settings;
+use crate::status::StatusKind;
+use crate::terrain::FieldCell;
+use crate::trap::{TrapKind, ALARM_HASTE_TIME, ALARM_RADIUS, FLASH_TIME, NET_TIME, SPIKE_STATS};
 
 use bracket_pathfinding::prelude::*;
-use bracket_terminal::prelude::{BTerm, GameState, VirtualKeyCode};
+use bracket_terminal::prelude::{BTerm, GameState};
 use std::cell::RefCell;
 use std::collections::HashSet;
 use std::rc::Rc;
 
+const MAP_WIDTH: usize = 80;
+const MAP_HEIGHT: usize = 25;
+/// Time the lantern of the dead player goes out
+const LIGHT_FADE_MS: f64 = 1000.0;
+
 pub enum StepperStatus {
     Finished,
     Pending,
@@ -20,16 +41,80 @@ pub trait Stepper {
     fn process(&mut self, world: &State, ctx: &BTerm) -> StepperStatus;
 }
 
+/// How the input is interpreted
+#[derive(Clone, Copy)]
+enum Mode {
+    /// Choose the character before the run
+    Create {
+        cursor: usize,
+    },
+    Play,
+    /// Examine cells with a cursor, the time is not running
+    Look {
+        cursor: Point,
+    },
+    /// Choose a target for the ranged attack among visible cells
+    Target {
+        cursor: Point,
+        attack: RangedAttack,
+    },
+    /// Choose a direction to dig
+    Dig,
+    /// Choose an advance for the new level, the time is not running
+    LevelUp {
+        cursor: usize,
+    },
+    /// The player is dead and the light fades out
+    Dying {
+        elapsed_ms: f64,
+    },
+    /// Summary of the game, waits for restart or exit
+    GameOver {
+        elapsed_ms: f64,
+    },
+}
+
 pub struct State {
     screen_width: u64,
     screen_height: u64,
     pub field: Field,
-    fov: HashSet<Point>,
+    pub fov: HashSet<Point>,
     pub player: Rc<RefCell<Player>>,
-    prev_player_pos: Point,
+    /// Player light at the last fov update, it moves together with the player
+    prev_player_light: LightSource,
+    /// Player sight radius at the last fov update, blindness changes it
+    prev_sight_radius: i32,
+    /// Player clock when decals were aged last time
+    prev_player_clock: f64,
     pub enemies: Vec<Rc<RefCell<Enemy>>>,
     current_stepper: Option<Rc<RefCell<dyn Stepper>>>,
-    pub blood_effect: RefCell<BloodParticlesEffect>,
+    pub particles: RefCell<ParticleSystem>,
+    pub keymap: Keymap,
+    show_help: bool,
+    mode: Mode,
+    pub items: RefCell<Vec<FloorItem>>,
+    pub projectiles: RefCell<Vec<Projectile>>,
+    pub log: RefCell<Log>,
+    pub animation_clock: AnimationClock,
+    pub animations: RefCell<Animations>,
+    /// Cells changed during the step, applied to the field after it
+    terrain_changes: RefCell<Vec<(Point, FieldCell)>>,
+    /// Traps stepped on during the step, they go off after it
+    triggered_traps: RefCell<Vec<Point>>,
+    themes: Themes,
+    settings: Settings,
+    pub rules: CombatRules,
+    /// Enemies killed during the game
+    kills: u32,
+}
+
+/// Move the cursor of a menu up or down, it wraps around
+fn menu_cursor(command: Option<Command>, cursor: usize, len: usize) -> usize {
+    match command {
+        Some(Command::Move(Direction::North)) => (cursor + len - 1) % len,
+        Some(Command::Move(Direction::South)) => (cursor + 1) % len,
+        _ => cursor,
+    }
 }
 
 fn remove_random<E>(v: &mut Vec<E>) -> E {
@@ -37,47 +122,381 @@ fn remove_random<E>(v: &mut Vec<E>) -> E {
 }
 
 fn create_enemies(empty_cells: &mut Vec<Point>) -> Vec<Rc<RefCell<Enemy>>> {
-    (0..25)
-        .into_iter()
-        .map(|_| {
+    let rats = (0..25).map(|_| EnemyBuilder::rat());
+    let goblins = (0..10).map(|_| EnemyBuilder::goblin());
+    let trolls = (0..5).map(|_| EnemyBuilder::troll());
+    let worms = (0..3).map(|_| EnemyBuilder::rock_worm());
+
+    rats.chain(goblins)
+        .chain(trolls)
+        .chain(worms)
+        .map(|builder| {
             let pos = remove_random(empty_cells);
-            let rat = EnemyBuilder::rat().pos(pos).build();
-            Rc::new(RefCell::new(rat))
+            Rc::new(RefCell::new(builder.pos(pos).build()))
         })
         .collect()
 }
 
+/// Items lying around, a key for every locked door and better loot in the vault
+fn create_items(
+    empty_cells: &mut Vec<Point>,
+    vault_cells: &mut Vec<Point>,
+    n_keys: usize,
+) -> Vec<FloorItem> {
+    let mut items = Vec::new();
+
+    for _ in 0..4 {
+        let count = 3 + rand::random::<u32>() % 4;
+        drop_item(
+            &mut items,
+            ItemKind::Arrow,
+            count,
+            remove_random(empty_cells),
+        );
+    }
+
+    for _ in 0..2 {
+        let count = 1 + rand::random::<u32>() % 2;
+        drop_item(
+            &mut items,
+            ItemKind::ThrowingKnife,
+            count,
+            remove_random(empty_cells),
+        );
+    }
+
+    for _ in 0..2 {
+        drop_item(
+            &mut items,
+            ItemKind::OilFlask,
+            1,
+            remove_random(empty_cells),
+        );
+    }
+
+    drop_item(&mut items, ItemKind::Pickaxe, 1, remove_random(empty_cells));
+
+    for _ in 0..n_keys {
+        drop_item(&mut items, ItemKind::Key, 1, remove_random(empty_cells));
+    }
+
+    let loot = [
+        (ItemKind::OilFlask, 3),
+        (ItemKind::ThrowingKnife, 6),
+        (ItemKind::Arrow, 20),
+    ];
+
+    for (kind, count) in loot.iter() {
+        if vault_cells.is_empty() {
+            break;
+        }
+
+        drop_item(&mut items, *kind, *count, remove_random(vault_cells));
+    }
+
+    items
+}
+
 impl State {
-    pub fn new(screen_width: u64, screen_height: u64) -> Self {
-        let field = Field::cave(80, 25, 0.6, 1);
+    pub fn new(
+        screen_width: u64,
+        screen_height: u64,
+        keymap: Keymap,
+        settings: Settings,
+        themes: Themes,
+    ) -> Self {
+        let x_scale = if settings.stretch { 2 } else { 1 };
+        let field = Field::cave(MAP_WIDTH, MAP_HEIGHT, 0.6, 1, x_scale);
+        let mut vault_cells = field.vault_cells();
         let mut empty_cells = field.empty_cells();
+        empty_cells.retain(|p| !vault_cells.contains(p));
 
-        let player = Player::new(remove_random(&mut empty_cells), 8);
-        let fov = field_of_view_set(player.pos(), player.view_radius, &field);
+        // replaced by the character chosen on the creation screen
+        let player = Player::new(remove_random(&mut empty_cells), Archetype::ALL[0]);
 
         let enemies = create_enemies(&mut empty_cells);
+        let n_keys = field.cells_of(FieldCell::LockedDoor).len();
+        let items = create_items(&mut empty_cells, &mut vault_cells, n_keys);
 
-        let blood_effect = BloodParticlesEffect::new();
-
-        State {
+        let mut state = State {
             screen_width,
             screen_height,
             field,
-            fov,
+            fov: HashSet::new(),
             enemies,
             current_stepper: None,
-            prev_player_pos: player.pos(),
+            prev_player_light: player.light(),
+            prev_sight_radius: player.sight_radius(),
+            prev_player_clock: player.clock(),
             player: Rc::new(RefCell::new(player)),
-            blood_effect: RefCell::new(blood_effect),
-        }
+            particles: RefCell::new(ParticleSystem::new()),
+            keymap,
+            show_help: false,
+            mode: Mode::Create { cursor: 0 },
+            items: RefCell::new(items),
+            projectiles: RefCell::new(Vec::new()),
+            log: RefCell::new(Log::default()),
+            animation_clock: AnimationClock::new(settings.time_scale),
+            animations: RefCell::new(Animations::new(settings.animations)),
+            terrain_changes: RefCell::new(Vec::new()),
+            triggered_traps: RefCell::new(Vec::new()),
+            themes,
+            rules: settings.combat,
+            settings,
+            kills: 0,
+        };
+
+        state.update_fov();
+        state
     }
 
+    /// Cells in the line of sight are seen if they are lit or next to the player.
+    /// Neighbour cells are seen even from inside of tall fungus
     fn update_fov(&mut self) {
         let player = self.player.borrow();
         let pos = player.pos();
+        let light = player.light();
 
-        self.fov = field_of_view_set(pos, player.view_radius, &self.field);
-        self.prev_player_pos = pos;
+        self.field.update_light(&[light]);
+
+        self.fov = field_of_view_set(pos, player.sight_radius(), &self.field)
+            .into_iter()
+            .filter(|p| {
+                DistanceAlg::Chebyshev.distance2d(pos, *p) <= 1.0
+                    || self.field.light_level(*p) >= MIN_VISIBLE_LIGHT
+            })
+            .collect();
+        let neighbours: Vec<_> = Direction::ALL
+            .iter()
+            .map(|d| pos + d.delta())
+            .filter(|p| self.field.cell(*p).is_some())
+            .collect();
+        self.fov.extend(neighbours);
+        self.field.explore(&self.fov);
+        self.prev_player_light = light;
+        self.prev_sight_radius = player.sight_radius();
+    }
+
+    /// Replace the cell when the current step is over
+    pub fn change_terrain(&self, p: Point, cell: FieldCell) {
+        self.terrain_changes.borrow_mut().push((p, cell));
+    }
+
+    /// Apply terrain changes, doors and walls may open or close the view
+    fn process_terrain_changes(&mut self) {
+        let changes: Vec<_> = self.terrain_changes.borrow_mut().drain(..).collect();
+
+        if changes.is_empty() {
+            return;
+        }
+
+        for (p, cell) in changes {
+            self.field.set_cell(p, cell);
+        }
+
+        self.update_fov();
+    }
+
+    pub fn trigger_trap(&self, p: Point) {
+        self.triggered_traps.borrow_mut().push(p);
+    }
+
+    fn process_traps(&mut self) {
+        let triggered: Vec<_> = self.triggered_traps.borrow_mut().drain(..).collect();
+
+        for p in triggered {
+            self.resolve_trap(p);
+        }
+    }
+
+    /// Random floor cell where nobody stands
+    fn free_cell(&self) -> Option<Point> {
+        let vault = self.field.vault_cells();
+        let mut cells: Vec<_> = self
+            .field
+            .empty_cells()
+            .into_iter()
+            .filter(|p| {
+                !vault.contains(p)
+                    && self.field.trap_at(*p).is_none()
+                    && self.enemy_at(*p).is_none()
+                    && self.player.borrow().pos() != *p
+            })
+            .collect();
+
+        if cells.is_empty() {
+            None
+        } else {
+            Some(remove_random(&mut cells))
+        }
+    }
+
+    /// Apply the trap to the player or the enemy standing on it.
+    /// Traps going off in view become known
+    fn resolve_trap(&self, p: Point) {
+        let trap = match self.field.trap_at(p) {
+            Some(trap) => trap,
+            None => return,
+        };
+
+        let enemy = if self.player.borrow().pos() == p {
+            None
+        } else {
+            match self.enemy_at(p) {
+                Some(enemy) => Some(enemy),
+                None => return,
+            }
+        };
+
+        let seen = enemy.is_none() || self.can_see(p);
+        let name = match &enemy {
+            Some(enemy) => format!("the {}", enemy.borrow().name),
+            None => "you".to_string(),
+        };
+
+        if seen {
+            trap.reveal();
+        }
+
+        if enemy.is_none() {
+            self.player.borrow_mut().interrupt();
+        }
+
+        let mut log = self.log.borrow_mut();
+
+        match trap.kind {
+            TrapKind::Spike => {
+                self.particles
+                    .borrow_mut()
+                    .burst(ParticleKind::Blood, p, 3, 0.5);
+                let mut animations = self.animations.borrow_mut();
+                if seen {
+                    animations.hit_flash(&self.fov, p);
+                }
+
+                let blow = match &enemy {
+                    Some(enemy) => {
+                        let mut enemy = enemy.borrow_mut();
+                        let blow = Blow::roll(
+                            self.rules,
+                            &SPIKE_STATS,
+                            &enemy.combat_stats(),
+                            &enemy.health,
+                        );
+
+                        if blow.kill {
+                            enemy.kill();
+                            if seen {
+                                let (glyph, color) = enemy.appearance();
+                                animations.death_fade(&self.fov, p, glyph, color);
+                            }
+                        } else {
+                            enemy.health.wound(blow.damage);
+                        }
+
+                        if let Some(affliction) = blow.affliction {
+                            enemy.statuses.add(affliction.kind, affliction.duration);
+                        }
+
+                        blow
+                    }
+                    None => {
+                        let mut player = self.player.borrow_mut();
+                        let blow = Blow::roll(
+                            self.rules,
+                            &SPIKE_STATS,
+                            &player.combat_stats(),
+                            &player.health,
+                        );
+
+                        if blow.kill {
+                            player.kill();
+                        } else {
+                            player.health.wound(blow.damage);
+                        }
+
+                        if let Some(affliction) = blow.affliction {
+                            player.statuses.add(affliction.kind, affliction.duration);
+                        }
+
+                        blow
+                    }
+                };
+
+                if seen && blow.kill {
+                    log.add(format!("The spikes kill {}.", name));
+                } else if seen && blow.damage > 0 {
+                    log.add(format!("The spikes wound {}.", name));
+                } else if seen {
+                    log.add(format!("The spikes graze {}.", name));
+                }
+
+                if let (None, Some(affliction)) = (&enemy, blow.affliction) {
+                    log.add(format!("You are {}.", affliction.kind.adjective()));
+                }
+            }
+            TrapKind::Alarm => {
+                log.add("An alarm rings!");
+
+                for enemy in &self.enemies {
+                    let mut enemy = enemy.borrow_mut();
+
+                    if DistanceAlg::Pythagoras.distance2d(enemy.pos(), p) <= ALARM_RADIUS {
+                        enemy.alert(p);
+                        enemy.statuses.add(StatusKind::Haste, ALARM_HASTE_TIME);
+                    }
+                }
+            }
+            TrapKind::Teleport => {
+                let target = match self.free_cell() {
+                    Some(target) => target,
+                    None => return,
+                };
+
+                let mut particles = self.particles.borrow_mut();
+                particles.burst(ParticleKind::Magic, p, 6, 0.4);
+                particles.burst(ParticleKind::Magic, target, 6, 0.4);
+
+                match &enemy {
+                    Some(enemy) => enemy.borrow_mut().teleport(target),
+                    None => self.player.borrow_mut().teleport(target),
+                }
+
+                if seen {
+                    log.add(format!("The trap sends {} away.", name));
+                }
+            }
+            TrapKind::Net => {
+                match &enemy {
+                    Some(enemy) => enemy.borrow_mut().delay(NET_TIME),
+                    None => self.player.borrow_mut().delay(NET_TIME),
+                }
+
+                if seen {
+                    log.add(format!("A net falls on {}.", name));
+                }
+            }
+            TrapKind::Flash => {
+                self.particles
+                    .borrow_mut()
+                    .burst(ParticleKind::Spark, p, 8, 0.5);
+
+                match &enemy {
+                    Some(enemy) => {
+                        let mut enemy = enemy.borrow_mut();
+                        enemy.statuses.add(StatusKind::Blindness, FLASH_TIME);
+                    }
+                    None => {
+                        let mut player = self.player.borrow_mut();
+                        player.statuses.add(StatusKind::Blindness, FLASH_TIME);
+                    }
+                }
+
+                if seen {
+                    log.add(format!("A flash of light blinds {}.", name));
+                }
+            }
+        }
     }
 
     fn next_stepper(&self) -> Option<Rc<RefCell<dyn Stepper>>> {
@@ -96,19 +515,430 @@ impl State {
         Some(stepper)
     }
 
-    fn process(&self, ctx: &mut BTerm) {
-        if let Some(VirtualKeyCode::Q) = ctx.key {
-            ctx.quit()
+    /// Alive enemy at the position
+    pub fn enemy_at(&self, pos: Point) -> Option<Rc<RefCell<Enemy>>> {
+        self.enemies
+            .iter()
+            .find(|e| match e.try_borrow() {
+                Err(_) => false,
+                Ok(e) => e.pos() == pos && !e.is_dead(),
+            })
+            .cloned()
+    }
+
+    /// Positions of enemies seen by the player, the nearest first
+    fn visible_enemies(&self) -> Vec<Point> {
+        let player = self.player.borrow().pos();
+
+        let mut enemies: Vec<_> = self
+            .enemies
+            .iter()
+            .map(|e| e.borrow().pos())
+            .filter(|p| self.fov.contains(p))
+            .collect();
+
+        enemies.sort_by_key(|p| DistanceAlg::PythagorasSquared.distance2d(player, *p) as i32);
+        enemies
+    }
+
+    pub fn can_see(&self, p: Point) -> bool {
+        self.fov.contains(&p)
+    }
+
+    /// Check if any enemy is seen by the player
+    pub fn enemy_in_view(&self) -> bool {
+        self.enemies.iter().any(|e| match e.try_borrow() {
+            Err(_) => false,
+            Ok(e) => self.fov.contains(&e.pos()),
+        })
+    }
+
+    /// Describe what the player knows about the cell
+    fn describe(&self, pos: Point) -> Vec<String> {
+        let mut lines = Vec::new();
+
+        if self.player.borrow().pos() == pos {
+            lines.push("you".to_string());
+        }
+
+        if self.fov.contains(&pos) {
+            for enemy in &self.enemies {
+                let enemy = enemy.borrow();
+
+                if enemy.pos() == pos {
+                    let mut line = format!("{}, {}", enemy.name, enemy.behaviour.description());
+
+                    if self.rules == CombatRules::Health {
+                        line = format!("{}, {}", line, enemy.health.apparent());
+                    }
+
+                    if enemy.statuses.has_any() {
+                        line = format!("{}, {}", line, enemy.statuses.description());
+                    }
+
+                    lines.push(line);
+                }
+            }
+
+            for item in self.items.borrow().iter() {
+                if item.pos == pos {
+                    lines.push(item.description());
+                }
+            }
+        }
+
+        if let Some(terrain) = self.field.describe(pos) {
+            if self.fov.contains(&pos) {
+                lines.push(terrain);
+            } else {
+                lines.push(format!("{} (remembered)", terrain));
+            }
+        }
+
+        lines
+    }
+
+    pub fn command(&self, ctx: &BTerm) -> Option<Command> {
+        ctx.key.and_then(|key| self.keymap.command(key))
+    }
+
+    fn process(&mut self, ctx: &mut BTerm) {
+        let command = self.command(ctx);
+
+        match command {
+            Some(Command::Quit) => ctx.quit(),
+            Some(Command::Help) => self.show_help = !self.show_help,
+            Some(Command::NextTheme) => {
+                let theme = self.themes.next();
+                self.log
+                    .borrow_mut()
+                    .add(format!("Color theme: {}.", theme.name.replace('_', " ")));
+            }
+            Some(Command::Cancel) if self.show_help => {
+                self.show_help = false;
+                return;
+            }
+            _ => (),
+        }
+
+        if self.show_help {
+            return;
+        }
+
+        match self.mode {
+            Mode::Play => {
+                if self.is_player_current_stepper() {
+                    self.process_mode_change(command);
+                }
+            }
+            Mode::Create { cursor } => self.process_create(command, cursor),
+            Mode::LevelUp { cursor } => self.process_level_up(command, cursor),
+            Mode::Look { cursor } => self.process_look(command, cursor),
+            Mode::Target { cursor, attack } => self.process_target(command, cursor, attack),
+            Mode::Dig => self.process_dig(command),
+            Mode::Dying { .. } => (),
+            Mode::GameOver { .. } => {
+                if let Some(Command::Restart) = command {
+                    self.restart();
+                }
+            }
+        }
+    }
+
+    /// Start a new game with the same screen and settings
+    fn restart(&mut self) {
+        *self = State::new(
+            self.screen_width,
+            self.screen_height,
+            self.keymap.clone(),
+            self.settings.clone(),
+            self.themes.clone(),
+        );
+    }
+
+    /// Fade the lantern of the dead player and then show the summary
+    fn process_death(&mut self) {
+        let delta = self.animation_clock.delta_ms();
+
+        match self.mode {
+            Mode::Play if self.player.borrow().is_dead() => {
+                self.mode = Mode::Dying { elapsed_ms: 0.0 };
+            }
+            Mode::Dying { elapsed_ms } => {
+                let elapsed_ms = elapsed_ms + delta;
+                self.player.borrow_mut().light_scale = (1.0 - elapsed_ms / LIGHT_FADE_MS).max(0.0);
+
+                self.mode = if elapsed_ms < LIGHT_FADE_MS {
+                    Mode::Dying { elapsed_ms }
+                } else {
+                    Mode::GameOver { elapsed_ms: 0.0 }
+                };
+            }
+            Mode::GameOver { elapsed_ms } => {
+                self.mode = Mode::GameOver {
+                    elapsed_ms: elapsed_ms + delta,
+                };
+            }
+            _ => (),
+        }
+    }
+
+    fn draw_game_over(&self, ctx: &mut BTerm, elapsed_ms: f64) {
+        let keys = |command| {
+            let keys: Vec<_> = self
+                .keymap
+                .keys(command)
+                .into_iter()
+                .map(key_name)
+                .collect();
+            keys.join(", ")
+        };
+
+        let hint = vec![
+            format!("Press {} to exit", keys(Command::Quit)),
+            format!("      {} to restart", keys(Command::Restart)),
+        ];
+
+        let player = self.player.borrow();
+        let summary = GameSummary {
+            kills: self.kills,
+            archetype: player.archetype.name(),
+            level: player.level,
+            experience: player.experience,
+            perks: player.perks.iter().map(|p| p.name()).collect(),
+        };
+        summary.draw(ctx, elapsed_ms, self.screen_height, &hint);
+    }
+
+    fn process_mode_change(&mut self, command: Option<Command>) {
+        if self.player.borrow().has_advances() {
+            let level = self.player.borrow().advance_level();
+            self.log
+                .borrow_mut()
+                .add(format!("You reach level {}!", level));
+            self.mode = Mode::LevelUp { cursor: 0 };
+            return;
+        }
+
+        let attack = match command {
+            Some(Command::Look) => {
+                let cursor = self.player.borrow().pos();
+                self.mode = Mode::Look { cursor };
+                return;
+            }
+            Some(Command::Dig) => {
+                if self.player.borrow().inventory.count(ItemKind::Pickaxe) > 0 {
+                    self.mode = Mode::Dig;
+                } else {
+                    self.log.borrow_mut().add("You have no pickaxe.");
+                }
+                return;
+            }
+            Some(Command::Fire) => RangedAttack::Fire,
+            Some(Command::Throw) => RangedAttack::Throw,
+            _ => return,
+        };
+
+        if !self.player.borrow().can_attack(attack) {
+            let ammo = attack.ammo().plural();
+            self.log
+                .borrow_mut()
+                .add(format!("You can not use {}.", ammo));
+            return;
+        }
+
+        let cursor = match self.visible_enemies().first() {
+            Some(enemy) => *enemy,
+            None => self.player.borrow().pos(),
+        };
+
+        self.mode = Mode::Target { cursor, attack };
+    }
+
+    fn process_create(&mut self, command: Option<Command>, cursor: usize) {
+        if let Some(Command::Confirm) = command {
+            let archetype = Archetype::ALL[cursor];
+            let pos = self.player.borrow().pos();
+            *self.player.borrow_mut() = Player::new(pos, archetype);
+            self.update_fov();
+
+            self.log
+                .borrow_mut()
+                .add(format!("You enter the caves as a {}.", archetype.name()));
+            self.mode = Mode::Play;
+        } else {
+            let cursor = menu_cursor(command, cursor, Archetype::ALL.len());
+            self.mode = Mode::Create { cursor };
+        }
+    }
+
+    fn process_level_up(&mut self, command: Option<Command>, cursor: usize) {
+        let mut player = self.player.borrow_mut();
+        let choices = Advance::choices(&player.perks);
+
+        if let Some(Command::Confirm) = command {
+            player.advance(choices[cursor]);
+            self.mode = Mode::Play;
+        } else {
+            let cursor = menu_cursor(command, cursor, choices.len());
+            self.mode = Mode::LevelUp { cursor };
+        }
+    }
+
+    fn process_dig(&mut self, command: Option<Command>) {
+        match command {
+            Some(Command::Move(direction)) => {
+                let mut player = self.player.borrow_mut();
+                let target = player.pos() + direction.delta();
+                player.dig(target);
+                self.mode = Mode::Play;
+            }
+            Some(Command::Cancel) | Some(Command::Dig) => self.mode = Mode::Play,
+            _ => (),
+        }
+    }
+
+    fn process_target(&mut self, command: Option<Command>, cursor: Point, attack: RangedAttack) {
+        match command {
+            Some(Command::Move(direction)) => {
+                let next = cursor + direction.delta();
+
+                if self.fov.contains(&next) {
+                    self.mode = Mode::Target {
+                        cursor: next,
+                        attack,
+                    };
+                }
+            }
+            Some(Command::NextTarget) => {
+                let enemies = self.visible_enemies();
+                let next = enemies
+                    .iter()
+                    .position(|p| *p == cursor)
+                    .map(|i| (i + 1) % enemies.len())
+                    .unwrap_or(0);
+
+                if let Some(enemy) = enemies.get(next) {
+                    self.mode = Mode::Target {
+                        cursor: *enemy,
+                        attack,
+                    };
+                }
+            }
+            Some(Command::Confirm) | Some(Command::Fire) | Some(Command::Throw)
+                if cursor != self.player.borrow().pos() =>
+            {
+                self.player.borrow_mut().aim(attack, cursor);
+                self.mode = Mode::Play;
+            }
+            Some(Command::Cancel) => self.mode = Mode::Play,
+            _ => (),
+        }
+    }
+
+    /// Move projectiles and apply results of finished flights
+    fn process_projectiles(&mut self) {
+        let finished: Vec<_> = {
+            let mut projectiles = self.projectiles.borrow_mut();
+
+            for projectile in projectiles.iter_mut() {
+                projectile.update(self.animation_clock.delta_ms());
+            }
+
+            let (finished, flying) = projectiles.drain(..).partition(|p| p.is_finished());
+            *projectiles = flying;
+            finished
+        };
+
+        for projectile in finished {
+            self.resolve_projectile(projectile);
+        }
+    }
+
+    fn resolve_projectile(&self, projectile: Projectile) {
+        let mut log = self.log.borrow_mut();
+        let name = projectile.kind.name();
+        let end = projectile.end();
+
+        match &projectile.hit {
+            Some(enemy) => {
+                let mut enemy = enemy.borrow_mut();
+
+                self.particles.borrow_mut().spawn(
+                    ParticleKind::Blood,
+                    end,
+                    projectile.direction(),
+                    3,
+                    0.8,
+                );
+
+                let mut animations = self.animations.borrow_mut();
+                animations.hit_flash(&self.fov, end);
+
+                if projectile.kill {
+                    enemy.kill();
+                    self.player
+                        .borrow_mut()
+                        .gain_experience(kill_experience(&enemy.stats));
+
+                    let (glyph, color) = enemy.appearance();
+                    animations.death_fade(&self.fov, end, glyph, color);
+                    log.add(format!("The {} kills the {}.", name, enemy.name));
+                } else {
+                    enemy.health.wound(projectile.damage);
+                    log.add(format!("The {} hits the {}.", name, enemy.name));
+
+                    if let Some(affliction) = projectile.affliction {
+                        enemy.statuses.add(affliction.kind, affliction.duration);
+                    }
+                }
+            }
+            None => {
+                if let Some(enemy) = projectile.missed {
+                    log.add(format!("The {} misses the {}.", name, enemy));
+                }
+
+                if projectile.hit_wall {
+                    let (x, y) = projectile.direction();
+                    self.particles
+                        .borrow_mut()
+                        .spawn(ParticleKind::Spark, end, (-x, -y), 4, 0.5);
+                }
+            }
+        }
+
+        if projectile.breaks {
+            log.add(format!("The {} breaks.", name));
+        } else {
+            drop_item(&mut self.items.borrow_mut(), projectile.kind, 1, end);
+        }
+    }
+
+    fn process_look(&mut self, command: Option<Command>, cursor: Point) {
+        match command {
+            Some(Command::Move(direction)) => {
+                let next = cursor + direction.delta();
+
+                if next.x >= 0
+                    && next.y >= 0
+                    && (next.x as usize) < MAP_WIDTH
+                    && (next.y as usize) < MAP_HEIGHT
+                {
+                    self.mode = Mode::Look { cursor: next };
+                }
+            }
+            Some(Command::Look) | Some(Command::Cancel) => self.mode = Mode::Play,
+            _ => (),
         }
     }
 
     fn process_stepper(&mut self, ctx: &mut BTerm) {
-        if let None = self.current_stepper {
+        if self.current_stepper.is_none() {
             self.current_stepper = self.next_stepper();
         }
 
         let status = match &self.current_stepper {
-            Some(stepper) => stepper.borrow_mut().process(&self, ctx),
+            Some(stepper) => stepper.borrow_mut().process(self, ctx),
             _ => StepperStatus::Finished,
         };
 
@@ -127,8 +957,248 @@ impl State {
         }
     }
 
+    /// Status line and messages under the map
+    fn draw_hud(&self, ctx: &mut BTerm) {
+        let player = self.player.borrow();
+        let mut status = format!(
+            "Lantern: {:.0}  {}",
+            player.fuel,
+            player.inventory.description()
+        );
+
+        let ability = player.archetype.ability().name();
+        let cooldown = player.ability_cooldown();
+        if cooldown > 0.0 {
+            status.insert_str(0, &format!("{} in {:.0}  ", ability, cooldown.ceil()));
+        } else {
+            status.insert_str(0, &format!("{} ready  ", ability));
+        }
+
+        if player.encumbrance() > 1.0 {
+            status.insert_str(0, "burdened  ");
+        }
+
+        if player.statuses.has_any() {
+            status.insert_str(0, &format!("{}  ", player.statuses.description()));
+        }
+
+        if self.rules == CombatRules::Health {
+            let health = format!("HP: {}/{}  ", player.health.hp(), player.health.max_hp());
+            status.insert_str(0, &health);
+        }
+
+        ctx.print(0, MAP_HEIGHT, status);
+
+        self.log.borrow().draw(ctx, Point::new(0, MAP_HEIGHT + 1));
+    }
+
+    /// Hint about the current mode at the right side of the status line
+    fn draw_hint(&self, ctx: &mut BTerm, hint: &str) {
+        ctx.print_right(self.screen_width, MAP_HEIGHT, hint)
+    }
+
     fn draw_wait(&self, ctx: &mut BTerm) {
-        ctx.print_centered(self.screen_height - 2, "[wait]")
+        self.draw_hint(ctx, "[wait]")
+    }
+
+    /// Describe the cell under the mouse cursor
+    fn draw_mouse_tooltip(&self, ctx: &mut BTerm) {
+        let mouse = ctx.mouse_point();
+
+        if mouse.x < 0
+            || mouse.y < 0
+            || mouse.x as u64 >= self.screen_width
+            || mouse.y as u64 >= self.screen_height
+        {
+            return;
+        }
+
+        let lines = self.describe(mouse);
+
+        if !lines.is_empty() {
+            self.draw_tooltip(ctx, mouse, &lines);
+        }
+    }
+
+    fn draw_look(&self, ctx: &mut BTerm, cursor: Point) {
+        ctx.set_bg(cursor.x, cursor.y, palette().cursor);
+
+        let mut lines = self.describe(cursor);
+
+        if lines.is_empty() {
+            lines.push("unexplored".to_string());
+        }
+
+        self.draw_tooltip(ctx, cursor, &lines);
+        self.draw_hint(ctx, "[look] Esc to exit");
+    }
+
+    fn draw_target(&self, ctx: &mut BTerm, cursor: Point, attack: RangedAttack) {
+        let player = self.player.borrow();
+
+        for p in line2d(LineAlg::Bresenham, player.pos(), cursor)
+            .into_iter()
+            .skip(1)
+        {
+            ctx.set_bg(p.x, p.y, palette().target_line);
+        }
+
+        ctx.set_bg(cursor.x, cursor.y, palette().cursor);
+
+        let mut lines = self.describe(cursor);
+
+        if let Some(enemy) = self.enemy_at(cursor) {
+            let distance = DistanceAlg::Pythagoras.distance2d(player.pos(), cursor) as f64;
+            let prob = player.stats.hit_prob(&enemy.borrow().stats, distance);
+            lines.push(format!("chance to hit {:.0}%", prob * 100.0));
+        }
+
+        if !lines.is_empty() {
+            self.draw_tooltip(ctx, cursor, &lines);
+        }
+
+        let ammo = attack.ammo();
+        let hint = format!(
+            "[{}: {} left] Enter to shoot",
+            ammo.name(),
+            player.inventory.count(ammo)
+        );
+        self.draw_hint(ctx, &hint);
+    }
+
+    /// Box with the lines next to the position
+    fn draw_tooltip(&self, ctx: &mut BTerm, pos: Point, lines: &[String]) {
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0) as i32 + 2;
+        let height = lines.len() as i32 + 2;
+
+        let mut x = pos.x + 1;
+        if x + width > self.screen_width as i32 {
+            x = pos.x - width;
+        }
+        let y = i32::min(pos.y, self.screen_height as i32 - height);
+
+        ctx.draw_box(x, y, width - 1, height - 1, palette().player, palette().bg);
+
+        for (i, line) in lines.iter().enumerate() {
+            ctx.print(x + 1, y + 1 + i as i32, line);
+        }
+    }
+
+    /// Archetypes to choose from before the run
+    fn draw_create(&self, ctx: &mut BTerm, cursor: usize) {
+        let mut lines = Vec::new();
+        let mut selected = 0;
+
+        for (i, archetype) in Archetype::ALL.iter().enumerate() {
+            let stats = archetype.stats();
+            let ability = archetype.ability();
+            let mut stat_line = format!(
+                "  attack {:.1}, defence {:.1}, luck {:.1}",
+                stats.attack, stats.defence, stats.luck
+            );
+
+            if self.rules == CombatRules::Health {
+                stat_line = format!("{}, {} HP", stat_line, stats.max_hp);
+            }
+
+            if i == cursor {
+                selected = lines.len();
+            }
+
+            lines.push(format!("{}: {}", archetype.name(), archetype.description()));
+            lines.push(stat_line);
+            lines.push(format!("  {}", archetype.inventory().description()));
+            lines.push(format!("  {}: {}", ability.name(), ability.description()));
+            lines.push(String::new());
+        }
+
+        lines.push("Choose with movement keys and Enter".to_string());
+
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0) as u64 + 4;
+        let height = lines.len() as u64 + 3;
+        let x = self.screen_width.saturating_sub(width) / 2;
+        let y = self.screen_height.saturating_sub(height) / 2;
+
+        ctx.draw_box(x, y, width - 1, height - 1, palette().player, palette().bg);
+        ctx.print_centered(y, " Choose a character ");
+
+        for (i, line) in lines.iter().enumerate() {
+            let bg = if i == selected {
+                palette().cursor
+            } else {
+                palette().bg
+            };
+            ctx.print_color(x + 2, y + 2 + i as u64, palette().log, bg, line);
+        }
+    }
+
+    /// Advances to choose from on a level up
+    fn draw_level_up(&self, ctx: &mut BTerm, cursor: usize) {
+        let player = self.player.borrow();
+        let lines: Vec<_> = Advance::choices(&player.perks)
+            .into_iter()
+            .map(|advance| advance.description())
+            .collect();
+
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0) as u64 + 4;
+        let height = lines.len() as u64 + 3;
+        let x = self.screen_width.saturating_sub(width) / 2;
+        let y = self.screen_height.saturating_sub(height) / 2;
+
+        ctx.draw_box(x, y, width - 1, height - 1, palette().player, palette().bg);
+        ctx.print_centered(y, format!(" Level {} ", player.level));
+
+        for (i, line) in lines.iter().enumerate() {
+            let bg = if i == cursor {
+                palette().cursor
+            } else {
+                palette().bg
+            };
+            ctx.print_color(x + 2, y + 2 + i as u64, palette().log, bg, line);
+        }
+
+        self.draw_hint(ctx, "[level up] choose with movement keys and Enter");
+    }
+
+    fn draw_help(&self, ctx: &mut BTerm) {
+        let lines: Vec<_> = Command::all()
+            .into_iter()
+            .map(|command| {
+                let keys: Vec<_> = self
+                    .keymap
+                    .keys(command)
+                    .into_iter()
+                    .map(key_name)
+                    .collect();
+                format!("{:<18} {}", command.description(), keys.join(", "))
+            })
+            .collect();
+
+        // Lines go in columns when they do not fit the screen height
+        let max_rows = usize::max(self.screen_height.saturating_sub(3) as usize, 1);
+        let n_columns = usize::max(lines.len().div_ceil(max_rows), 1);
+        let rows = lines.len().div_ceil(n_columns);
+        let columns: Vec<_> = lines.chunks(rows).collect();
+        let column_widths: Vec<_> = columns
+            .iter()
+            .map(|c| c.iter().map(|l| l.len()).max().unwrap_or(0) as u64)
+            .collect();
+
+        let width = column_widths.iter().sum::<u64>() + 2 * columns.len() as u64 + 2;
+        let height = rows as u64 + 3;
+        let x = self.screen_width.saturating_sub(width) / 2;
+        let y = self.screen_height.saturating_sub(height) / 2;
+
+        ctx.draw_box(x, y, width - 1, height - 1, palette().player, palette().bg);
+        ctx.print_centered(y, " Keys ");
+
+        let mut column_x = x + 2;
+        for (column, column_width) in columns.iter().zip(column_widths) {
+            for (i, line) in column.iter().enumerate() {
+                ctx.print(column_x, y + 2 + i as u64, line);
+            }
+            column_x += column_width + 2;
+        }
     }
 }
 
@@ -137,26 +1207,99 @@ impl GameState for State {
         ctx.cls();
 
         self.process(ctx);
-        self.blood_effect.borrow_mut().process();
-        self.process_stepper(ctx);
 
-        if self.prev_player_pos != self.player.borrow().pos() {
+        self.animation_clock.set_paused(self.show_help);
+        self.animation_clock.advance(ctx.frame_time_ms);
+        let decals = self.particles.borrow_mut().process(self);
+        for (p, decal) in decals {
+            self.field.add_decal(p, decal);
+        }
+
+        let flying = !self.projectiles.borrow().is_empty();
+        self.process_projectiles();
+
+        let animating = self.animations.borrow().is_running();
+        self.animations.borrow_mut().process(&self.animation_clock);
+
+        if !self.show_help && !flying && !animating && matches!(self.mode, Mode::Play) {
+            self.process_stepper(ctx);
+        }
+
+        self.process_traps();
+        self.process_terrain_changes();
+        self.process_death();
+
+        self.kills += self.enemies.iter().filter(|e| e.borrow().is_dead()).count() as u32;
+        self.enemies.retain(|e| !e.borrow().is_dead());
+
+        let clock = self.player.borrow().clock();
+        if clock > self.prev_player_clock {
+            self.field.age_decals(clock - self.prev_player_clock);
+            self.prev_player_clock = clock;
+        }
+
+        let player = self.player.borrow();
+        let fov_changed = self.prev_player_light != player.light()
+            || self.prev_sight_radius != player.sight_radius();
+        drop(player);
+
+        if fov_changed {
             self.update_fov();
         }
 
+        if let Mode::GameOver { elapsed_ms } = self.mode {
+            self.draw_game_over(ctx, elapsed_ms);
+            return;
+        }
+
+        if let Mode::Create { cursor } = self.mode {
+            self.draw_create(ctx, cursor);
+            return;
+        }
+
         self.field
             .draw_with_fov(ctx, &self.fov, Point::zero(), Point::zero());
 
+        for item in self.items.borrow().iter() {
+            item.draw_lit(ctx, &self.fov, &self.field, item.pos);
+        }
+
         for enemy in &self.enemies {
             let enemy = enemy.borrow();
-            enemy.draw_with_fov(ctx, &self.fov, enemy.pos(), enemy.pos());
+            enemy.draw_lit(ctx, &self.fov, &self.field, enemy.pos());
         }
 
-        self.player.borrow().draw(ctx, self.player.borrow().pos());
-        self.blood_effect.borrow().draw(ctx, Point::zero());
+        let player = self.player.borrow();
+        player.draw_lit(ctx, &self.fov, &self.field, player.pos());
+        self.particles
+            .borrow()
+            .draw_with_fov(ctx, &self.fov, Point::zero(), Point::zero());
+
+        for projectile in self.projectiles.borrow().iter() {
+            projectile.draw(ctx, Point::zero());
+        }
+
+        self.animations
+            .borrow()
+            .draw_with_fov(ctx, &self.fov, Point::zero(), Point::zero());
 
-        if !self.is_player_current_stepper() {
+        self.draw_hud(ctx);
+
+        if !self.is_player_current_stepper() || animating {
             self.draw_wait(ctx)
         }
+
+        if self.show_help {
+            self.draw_help(ctx)
+        } else {
+            match self.mode {
+                Mode::Play => self.draw_mouse_tooltip(ctx),
+                Mode::Look { cursor } => self.draw_look(ctx, cursor),
+                Mode::Target { cursor, attack } => self.draw_target(ctx, cursor, attack),
+                Mode::Dig => self.draw_hint(ctx, "[dig] choose a direction"),
+                Mode::LevelUp { cursor } => self.draw_level_up(ctx, cursor),
+                Mode::Create { .. } | Mode::Dying { .. } | Mode::GameOver { .. } => (),
+            }
+        }
     }
 }
@@ -0,0 +1,141 @@
+use crate::combat::CombatStats;
+
+/// Health points lost per turn
+const POISON_DRAIN: f64 = 0.3;
+const BLEEDING_DRAIN: f64 = 0.5;
+/// Blind creatures see only the cells next to them
+pub const BLIND_RADIUS: i32 = 1;
+
+/// Temporary condition of a creature
+#[derive(Clone, Copy, PartialEq)]
+pub enum StatusKind {
+    /// Drains health and weakens blows
+    Poison,
+    /// Drains health and weakens defence
+    Bleeding,
+    /// The creature loses its turns
+    Stun,
+    /// Actions take twice as long
+    Slow,
+    /// Actions take half as long
+    Haste,
+    /// The creature sees only what is next to it
+    Blindness,
+}
+
+impl StatusKind {
+    /// How the creature with the status is described, like "poisoned"
+    pub fn adjective(self) -> &'static str {
+        match self {
+            Self::Poison => "poisoned",
+            Self::Bleeding => "bleeding",
+            Self::Stun => "stunned",
+            Self::Slow => "slowed",
+            Self::Haste => "hasted",
+            Self::Blindness => "blinded",
+        }
+    }
+}
+
+/// Status a blow may leave on the target
+#[derive(Clone, Copy)]
+pub struct Affliction {
+    pub kind: StatusKind,
+    pub prob: f64,
+    /// Time the status lasts
+    pub duration: f64,
+}
+
+struct StatusEffect {
+    kind: StatusKind,
+    time_left: f64,
+}
+
+/// Statuses of a creature, they wear off with time
+#[derive(Default)]
+pub struct Statuses {
+    effects: Vec<StatusEffect>,
+}
+
+impl Statuses {
+    /// Add the status, a status already present lasts for the longer duration
+    pub fn add(&mut self, kind: StatusKind, duration: f64) {
+        match self.effects.iter_mut().find(|e| e.kind == kind) {
+            Some(effect) => effect.time_left = f64::max(effect.time_left, duration),
+            None => self.effects.push(StatusEffect {
+                kind,
+                time_left: duration,
+            }),
+        }
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|e| e.kind == kind)
+    }
+
+    pub fn has_any(&self) -> bool {
+        !self.effects.is_empty()
+    }
+
+    /// Let the time pass, returns health points drained meanwhile
+    pub fn tick(&mut self, time: f64) -> f64 {
+        let drain = self
+            .effects
+            .iter()
+            .map(|e| {
+                let rate = match e.kind {
+                    StatusKind::Poison => POISON_DRAIN,
+                    StatusKind::Bleeding => BLEEDING_DRAIN,
+                    _ => 0.0,
+                };
+                rate * f64::min(time, e.time_left)
+            })
+            .sum();
+
+        for effect in &mut self.effects {
+            effect.time_left -= time;
+        }
+        self.effects.retain(|e| e.time_left > 0.0);
+
+        drain
+    }
+
+    /// Multiplier of the time taken by actions
+    pub fn time_scale(&self) -> f64 {
+        let mut scale = 1.0;
+
+        if self.has(StatusKind::Slow) {
+            scale *= 2.0;
+        }
+        if self.has(StatusKind::Haste) {
+            scale *= 0.5;
+        }
+
+        scale
+    }
+
+    /// Combat stats weakened by the statuses
+    pub fn apply(&self, stats: CombatStats) -> CombatStats {
+        let mut stats = stats;
+
+        for effect in &self.effects {
+            match effect.kind {
+                StatusKind::Poison | StatusKind::Blindness => stats.attack *= 0.5,
+                StatusKind::Bleeding => stats.defence *= 0.75,
+                StatusKind::Stun => stats.defence *= 0.5,
+                StatusKind::Slow | StatusKind::Haste => {}
+            }
+        }
+
+        stats
+    }
+
+    /// List of the statuses, like "poisoned, slowed"
+    pub fn description(&self) -> String {
+        self.effects
+            .iter()
+            .map(|e| e.kind.adjective())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
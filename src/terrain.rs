@@ -0,0 +1,249 @@
+use crate::colors::{palette, Palette, PaletteColor, RGBA};
+use crate::draw::{BTerm, Draw, Point};
+
+/// Properties shared by all cells of a kind
+pub struct Terrain {
+    pub name: &'static str,
+    pub glyph: &'static str,
+    /// Creatures can step on the cell
+    pub walkable: bool,
+    /// The cell blocks the line of sight and the light
+    pub opaque: bool,
+    /// Projectiles and particles can not fly through the cell
+    pub solid: bool,
+    /// Time to step on the cell relative to the bare floor
+    pub move_cost: f64,
+    /// Blood and other marks stay on the cell
+    pub stains: bool,
+    color: PaletteColor,
+    bg: PaletteColor,
+}
+
+impl Terrain {
+    pub fn color(&self) -> RGBA {
+        (self.color)(&palette())
+    }
+
+    pub fn bg(&self) -> RGBA {
+        (self.bg)(&palette())
+    }
+}
+
+fn no_bg(p: &Palette) -> RGBA {
+    p.bg
+}
+
+const FLOOR: Terrain = Terrain {
+    name: "floor",
+    glyph: ".",
+    walkable: true,
+    opaque: false,
+    solid: false,
+    move_cost: 1.0,
+    stains: true,
+    color: |p| p.empty,
+    bg: no_bg,
+};
+
+const WALL: Terrain = Terrain {
+    name: "wall",
+    glyph: "█",
+    walkable: false,
+    opaque: true,
+    solid: true,
+    move_cost: 1.0,
+    stains: true,
+    color: |p| p.wall,
+    bg: no_bg,
+};
+
+const OPEN_DOOR: Terrain = Terrain {
+    name: "open door",
+    glyph: "'",
+    walkable: true,
+    opaque: false,
+    solid: false,
+    move_cost: 1.0,
+    stains: true,
+    color: |p| p.door,
+    bg: no_bg,
+};
+
+const CLOSED_DOOR: Terrain = Terrain {
+    name: "closed door",
+    glyph: "+",
+    walkable: false,
+    opaque: true,
+    solid: true,
+    move_cost: 1.0,
+    stains: true,
+    color: |p| p.door,
+    bg: no_bg,
+};
+
+const LOCKED_DOOR: Terrain = Terrain {
+    name: "locked door",
+    glyph: "+",
+    walkable: false,
+    opaque: true,
+    solid: true,
+    move_cost: 1.0,
+    stains: true,
+    color: |p| p.locked_door,
+    bg: no_bg,
+};
+
+const SHALLOW_WATER: Terrain = Terrain {
+    name: "shallow water",
+    glyph: "~",
+    walkable: true,
+    opaque: false,
+    solid: false,
+    move_cost: 2.0,
+    stains: false,
+    color: |p| p.water,
+    bg: |p| p.water_bg,
+};
+
+const DEEP_WATER: Terrain = Terrain {
+    name: "deep water",
+    glyph: "≈",
+    walkable: false,
+    opaque: false,
+    solid: false,
+    move_cost: 1.0,
+    stains: false,
+    color: |p| p.deep_water,
+    bg: |p| p.deep_water_bg,
+};
+
+const LAVA: Terrain = Terrain {
+    name: "lava",
+    glyph: "≈",
+    walkable: false,
+    opaque: false,
+    solid: false,
+    move_cost: 1.0,
+    stains: false,
+    color: |p| p.lava,
+    bg: |p| p.lava_bg,
+};
+
+const CHASM: Terrain = Terrain {
+    name: "chasm",
+    glyph: "▒",
+    walkable: false,
+    opaque: false,
+    solid: false,
+    move_cost: 1.0,
+    stains: false,
+    color: |p| p.chasm,
+    bg: no_bg,
+};
+
+const RUBBLE: Terrain = Terrain {
+    name: "rubble",
+    glyph: ":",
+    walkable: true,
+    opaque: false,
+    solid: false,
+    move_cost: 1.5,
+    stains: true,
+    color: |p| p.rubble,
+    bg: no_bg,
+};
+
+const GLASS_WALL: Terrain = Terrain {
+    name: "glass wall",
+    glyph: "▓",
+    walkable: false,
+    opaque: false,
+    solid: true,
+    move_cost: 1.0,
+    stains: true,
+    color: |p| p.glass,
+    bg: no_bg,
+};
+
+const IRON_BARS: Terrain = Terrain {
+    name: "iron bars",
+    glyph: "#",
+    walkable: false,
+    opaque: false,
+    solid: false,
+    move_cost: 1.0,
+    stains: false,
+    color: |p| p.iron_bars,
+    bg: no_bg,
+};
+
+/// Fungus higher than a man, it hides everything behind it
+const TALL_FUNGUS: Terrain = Terrain {
+    name: "tall fungus",
+    glyph: "♣",
+    walkable: true,
+    opaque: true,
+    solid: false,
+    move_cost: 1.5,
+    stains: false,
+    color: |p| p.fungus,
+    bg: no_bg,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FieldCell {
+    Empty,
+    Wall,
+    OpenDoor,
+    ClosedDoor,
+    LockedDoor,
+    ShallowWater,
+    DeepWater,
+    Lava,
+    Chasm,
+    Rubble,
+    GlassWall,
+    IronBars,
+    TallFungus,
+}
+
+impl FieldCell {
+    pub fn terrain(self) -> &'static Terrain {
+        match self {
+            Self::Empty => &FLOOR,
+            Self::Wall => &WALL,
+            Self::OpenDoor => &OPEN_DOOR,
+            Self::ClosedDoor => &CLOSED_DOOR,
+            Self::LockedDoor => &LOCKED_DOOR,
+            Self::ShallowWater => &SHALLOW_WATER,
+            Self::DeepWater => &DEEP_WATER,
+            Self::Lava => &LAVA,
+            Self::Chasm => &CHASM,
+            Self::Rubble => &RUBBLE,
+            Self::GlassWall => &GLASS_WALL,
+            Self::IronBars => &IRON_BARS,
+            Self::TallFungus => &TALL_FUNGUS,
+        }
+    }
+
+    /// Cell of a cave after a step of smoothing, only floor and walls are generated this way
+    pub fn smoothed(self, n_neighbour_walls: u32) -> Self {
+        match self {
+            Self::Empty | Self::Wall => {
+                if n_neighbour_walls > 4 {
+                    Self::Wall
+                } else {
+                    Self::Empty
+                }
+            }
+            _ => self,
+        }
+    }
+}
+
+impl Draw for FieldCell {
+    fn draw(&self, ctx: &mut BTerm, pos: Point) {
+        let terrain = self.terrain();
+        ctx.print_color(pos.x, pos.y, terrain.color(), terrain.bg(), terrain.glyph)
+    }
+}
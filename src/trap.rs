@@ -0,0 +1,94 @@
+use crate::colors::{palette, RGBA};
+use crate::combat::{CombatStats, Damage};
+use crate::draw::Point;
+use crate::status::{Affliction, StatusKind};
+use std::cell::Cell;
+
+/// Blow of the spikes to whoever steps on them
+pub const SPIKE_STATS: CombatStats = CombatStats {
+    attack: 0.15,
+    defence: 1.0,
+    luck: 0.0,
+    max_hp: 1,
+    damage: Damage { min: 2, max: 6 },
+    affliction: Some(Affliction {
+        kind: StatusKind::Bleeding,
+        prob: 0.5,
+        duration: 10.0,
+    }),
+};
+/// Enemies hear the alarm up to this distance
+pub const ALARM_RADIUS: f32 = 25.0;
+/// Time to get out of the net
+pub const NET_TIME: f64 = 5.0;
+/// Enemies rush to the alarm for a while
+pub const ALARM_HASTE_TIME: f64 = 10.0;
+/// Time the flash blinds its victim for
+pub const FLASH_TIME: f64 = 10.0;
+
+#[derive(Clone, Copy)]
+pub enum TrapKind {
+    /// Spikes may kill whoever steps on them
+    Spike,
+    /// Calls enemies from all around
+    Alarm,
+    /// Sends the victim to a random place
+    Teleport,
+    /// Holds the victim for several turns
+    Net,
+    /// Blinds the victim for a while
+    Flash,
+}
+
+impl TrapKind {
+    pub const ALL: [TrapKind; 5] = [
+        Self::Spike,
+        Self::Alarm,
+        Self::Teleport,
+        Self::Net,
+        Self::Flash,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Spike => "spike trap",
+            Self::Alarm => "alarm trap",
+            Self::Teleport => "teleport trap",
+            Self::Net => "net trap",
+            Self::Flash => "flash trap",
+        }
+    }
+
+    pub fn glyph(self) -> char {
+        '^'
+    }
+
+    pub fn color(self) -> RGBA {
+        palette().trap
+    }
+}
+
+/// Trap on the field, it is hidden until the player finds it
+pub struct Trap {
+    pub kind: TrapKind,
+    pub pos: Point,
+    hidden: Cell<bool>,
+}
+
+impl Trap {
+    pub fn new(kind: TrapKind, pos: Point) -> Self {
+        Self {
+            kind,
+            pos,
+            hidden: Cell::new(true),
+        }
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.hidden.get()
+    }
+
+    pub fn reveal(&self) {
+        self.hidden.set(false);
+    }
+}